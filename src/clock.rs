@@ -0,0 +1,143 @@
+use chrono::{DateTime, Duration, Utc};
+use std::time::Instant;
+
+/// Where the clock gets the current time from.
+///
+/// Only the frontend asks a source for the time; the renderer is always handed
+/// the time to draw, so a clock can be driven by a fake source in tests and demos.
+pub trait ClockSource: Send + Sync {
+    /// The current instant according to this source.
+    fn now(&self) -> DateTime<Utc>;
+
+    /// How many times as fast as real time the source runs, so callers can
+    /// convert waits until a simulated instant into real time.
+    fn speed(&self) -> f64 {
+        1.
+    }
+}
+
+/// The system's wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl ClockSource for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock stopped at one instant.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl ClockSource for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// A clock starting at `start` when created and running `speed` times as fast as
+/// real time.
+#[derive(Debug, Clone, Copy)]
+pub struct AcceleratedClock {
+    start: DateTime<Utc>,
+    origin: Instant,
+    speed: f64,
+}
+
+impl AcceleratedClock {
+    pub fn new(start: DateTime<Utc>, speed: f64) -> AcceleratedClock {
+        AcceleratedClock {
+            start,
+            origin: Instant::now(),
+            speed,
+        }
+    }
+}
+
+impl ClockSource for AcceleratedClock {
+    fn now(&self) -> DateTime<Utc> {
+        let elapsed = self.origin.elapsed().as_secs_f64() * self.speed;
+        self.start + Duration::nanoseconds((elapsed * 1e9) as i64)
+    }
+
+    fn speed(&self) -> f64 {
+        self.speed
+    }
+}
+
+/// Plays back a list of instants, each for `interval` of real time, then stays
+/// at the last one.
+#[derive(Debug, Clone)]
+pub struct ReplayClock {
+    times: Vec<DateTime<Utc>>,
+    interval: std::time::Duration,
+    origin: Instant,
+}
+
+impl ReplayClock {
+    /// Panics if `times` is empty.
+    pub fn new(times: Vec<DateTime<Utc>>, interval: std::time::Duration) -> ReplayClock {
+        assert!(!times.is_empty(), "nothing to replay");
+        ReplayClock {
+            times,
+            interval,
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl ClockSource for ReplayClock {
+    fn now(&self) -> DateTime<Utc> {
+        let index = self
+            .origin
+            .elapsed()
+            .as_nanos()
+            .checked_div(self.interval.as_nanos())
+            .unwrap_or(u128::MAX);
+        self.times[(index as usize).min(self.times.len() - 1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2021, 7, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn real_time_sources_run_at_speed_one() {
+        assert_eq!(SystemClock.speed(), 1.);
+        assert_eq!(AcceleratedClock::new(at(11, 58), 60.).speed(), 60.);
+    }
+
+    #[test]
+    fn fixed_clock_stands_still() {
+        let clock = FixedClock(at(11, 58));
+        assert_eq!(clock.now(), at(11, 58));
+        assert_eq!(clock.now(), at(11, 58));
+    }
+
+    #[test]
+    fn stopped_accelerated_clock_stays_at_its_start() {
+        let clock = AcceleratedClock::new(at(11, 58), 0.);
+        assert_eq!(clock.now(), at(11, 58));
+    }
+
+    #[test]
+    fn replay_starts_at_the_first_time_and_ends_at_the_last() {
+        let hour = std::time::Duration::from_secs(3600);
+        assert_eq!(
+            ReplayClock::new(vec![at(9, 0), at(10, 0)], hour).now(),
+            at(9, 0)
+        );
+        let instant = std::time::Duration::from_secs(0);
+        assert_eq!(
+            ReplayClock::new(vec![at(9, 0), at(10, 0)], instant).now(),
+            at(10, 0)
+        );
+    }
+}
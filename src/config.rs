@@ -0,0 +1,254 @@
+use gluqlo::{GluqloError, Result, Theme};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Keys allowed at the top level of the configuration file.
+const KEYS: &[&str] = &[
+    "width",
+    "height",
+    "scale",
+    "ampm",
+    "leading-zero",
+    "fullscreen",
+    "display",
+    "all-displays",
+    "per-display",
+    "seconds",
+    "date",
+    "fps",
+    "transition",
+    "animation",
+    "duration",
+    "shift",
+    "vsync",
+    "renderer",
+    "tz",
+    "world",
+    "theme",
+    "night",
+    "latitude",
+    "longitude",
+    "night-theme",
+    "night-brightness",
+    "font",
+    "font-family",
+    "digit-font",
+    "label-font",
+];
+
+/// Settings read from `config.toml`. Every key is optional; command line flags
+/// take precedence over the file.
+///
+/// ```toml
+/// width = 1920
+/// height = 1080
+/// scale = 0.8
+/// ampm = true
+/// leading-zero = false
+/// fullscreen = true
+/// # all-displays = true
+/// # per-display = "spread"
+/// seconds = false
+/// date = "%a %-d %b"
+/// fps = 60
+/// transition = "flip3d"
+/// duration = 260
+/// shift = "10"
+/// vsync = true
+/// renderer = "accelerated"
+/// tz = "Europe/Berlin"
+/// # world = ["Asia/Tokyo", "Europe/London", "America/New_York=NYC"]
+/// theme = "amber"
+/// night = "sun"
+/// latitude = 52.52
+/// longitude = 13.405
+/// night-brightness = 0.6
+/// font = "/usr/share/fonts/TTF/DejaVuSans.ttf"
+///
+/// [colors]
+/// card = "#202020"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub scale: Option<f32>,
+    pub ampm: Option<bool>,
+    pub leading_zero: Option<bool>,
+    pub fullscreen: Option<bool>,
+    /// Display to open the window on, like `--display`.
+    pub display: Option<i32>,
+    pub all_displays: Option<bool>,
+    /// `mirror` or `spread`, like `--per-display`.
+    pub per_display: Option<String>,
+    pub seconds: Option<bool>,
+    /// `strftime` format of the date line, like `--date`.
+    pub date: Option<String>,
+    pub fps: Option<u32>,
+    /// How cards turn over, like `--transition`; `animation` is accepted too.
+    #[serde(alias = "animation")]
+    pub transition: Option<String>,
+    /// Length of a transition in milliseconds.
+    pub duration: Option<u64>,
+    /// `flip` or a number of minutes, like `--shift`.
+    pub shift: Option<String>,
+    pub vsync: Option<bool>,
+    /// `software` or `accelerated`, like `--renderer`.
+    pub renderer: Option<String>,
+    pub tz: Option<String>,
+    /// World clock zones, written like `--world`.
+    #[serde(default)]
+    pub world: Vec<String>,
+    pub theme: Option<String>,
+    /// `HH:MM-HH:MM` or `sun`, like `--night`.
+    pub night: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub night_theme: Option<String>,
+    pub night_brightness: Option<f32>,
+    pub font: Option<PathBuf>,
+    pub font_family: Option<String>,
+    pub digit_font: Option<String>,
+    pub label_font: Option<String>,
+    /// Theme color overrides, keyed like `--color`.
+    #[serde(default)]
+    pub colors: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// Load `path` if given, which must then exist, or else the default
+    /// `$XDG_CONFIG_HOME/gluqlo/config.toml` if there is one.
+    pub fn load(path: Option<&Path>) -> Result<Config> {
+        let path = match path {
+            Some(path) => path.to_owned(),
+            None => match default_path().filter(|path| path.is_file()) {
+                Some(path) => path,
+                None => return Ok(Config::default()),
+            },
+        };
+
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| GluqloError::Config(format!("cannot read {}: {}", path.display(), e)))?;
+        for warning in unknown_keys(&text) {
+            eprintln!("gluqlo: warning: {}:{}", path.display(), warning);
+        }
+        toml::from_str(&text).map_err(|e| GluqloError::Config(format!("{}: {}", path.display(), e)))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/gluqlo/config.toml`, falling back to `~/.config`.
+fn default_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("gluqlo").join("config.toml"))
+}
+
+/// Describe every key the configuration does not know, as `LINE: message`.
+///
+/// Unknown keys are ignored when loading; this only scans `key =` and `[table]`
+/// lines so the warning can point at where the key was written.
+fn unknown_keys(text: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut table = String::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            table = line
+                .trim_matches(|c| c == '[' || c == ']')
+                .trim()
+                .to_owned();
+            if table != "colors" {
+                warnings.push(format!(
+                    "{}: unknown table [{}], expected [colors]",
+                    number + 1,
+                    table
+                ));
+            }
+            continue;
+        }
+
+        let key = match line.split_once('=') {
+            Some((key, _)) => key.trim().trim_matches('"'),
+            None => continue,
+        };
+        // Not a key but a continued array such as `"America/New_York=NYC",`.
+        if !key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        {
+            continue;
+        }
+        let known = match table.as_str() {
+            "" => KEYS.contains(&key),
+            "colors" => Theme::KEYS.contains(&key),
+            // Already reported for the table header.
+            _ => true,
+        };
+        if !known {
+            let expected = if table.is_empty() { KEYS } else { Theme::KEYS };
+            warnings.push(format!(
+                "{}: unknown key `{}`, expected one of {}",
+                number + 1,
+                key,
+                expected.join(", ")
+            ));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_all_keys() {
+        let config: Config = toml::from_str(
+            "width = 800\nscale = 0.5\nleading-zero = true\ntheme = \"light\"\n\n[colors]\ncard = \"#202020\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.width, Some(800));
+        assert_eq!(config.scale, Some(0.5));
+        assert_eq!(config.leading_zero, Some(true));
+        assert_eq!(config.ampm, None);
+        assert_eq!(config.theme.as_deref(), Some("light"));
+        assert_eq!(config.colors["card"], "#202020");
+    }
+
+    #[test]
+    fn parses_world_clocks() {
+        let text = "tz = \"Europe/Berlin\"\nworld = [\n  \"Asia/Tokyo\",\n  \"America/New_York=NYC\",\n]\n";
+        let config: Config = toml::from_str(text).unwrap();
+        assert_eq!(config.tz.as_deref(), Some("Europe/Berlin"));
+        assert_eq!(config.world, ["Asia/Tokyo", "America/New_York=NYC"]);
+        assert!(unknown_keys(text).is_empty());
+    }
+
+    #[test]
+    fn accepts_animation_for_transition() {
+        let config: Config = toml::from_str("animation = \"slide\"\nduration = 400\n").unwrap();
+        assert_eq!(config.transition.as_deref(), Some("slide"));
+        assert_eq!(config.duration, Some(400));
+        assert!(unknown_keys("animation = \"slide\"\ntransition = \"roll\"\n").is_empty());
+    }
+
+    #[test]
+    fn warns_about_unknown_keys_with_line_numbers() {
+        let warnings =
+            unknown_keys("width = 800\n# comment\nwidht = 1\n[colors]\ndigit = \"#fff\"\n");
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("3: unknown key `widht`"));
+        assert!(warnings[1].starts_with("5: unknown key `digit`"));
+    }
+}
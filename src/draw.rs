@@ -0,0 +1,78 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::surface::SurfaceRef;
+
+/// Fill a box with rounded corners of radius `r` into `dst`, which must be a 32-bit surface.
+pub(crate) fn fill_rounded_box_b(dst: &mut SurfaceRef, coords: &Rect, r: i32, color: Color) {
+    let pixcolor = color.to_u32(&dst.pixel_format());
+    let rpsqrt2 = (r as f64 / 2.0_f64.sqrt()) as i32;
+    let yd: i32 = dst.pitch() as i32 / dst.pixel_format_enum().byte_size_per_pixel() as i32;
+    let mut w: i32 = coords.width() as i32 / 2 - 1;
+    let mut h: i32 = coords.height() as i32 / 2 - 1;
+    let xo = coords.x() + w;
+    let yo = coords.y() + h;
+
+    w -= r;
+    h -= r;
+
+    if w <= 0 || h <= 0 {
+        return;
+    }
+
+    dst.with_lock_mut(|pixels| {
+        let (_prefix, pixels, _suffix) = unsafe { pixels.align_to_mut::<u32>() };
+
+        let sy: i32 = (yo - h) * yd;
+        let ey: i32 = (yo + h) * yd;
+        let sx: i32 = xo - w;
+        let ex: i32 = xo + w;
+
+        for i in (sy..=ey).step_by(yd as usize) {
+            for j in (sx - r)..=(ex + r) {
+                // let index = (i + j) as usize;
+                // pixels[index + 0] = color.r;
+                // pixels[index + 1] = color.g;
+                // pixels[index + 2] = color.b;
+                // pixels[index + 3] = color.a;
+
+                // 如果我没理解错，就是一次赋4个值
+                //                set_pixels(pixels, i + j, pixcolor);
+                pixels[(i + j) as usize] = pixcolor;
+            }
+        }
+
+        let mut d: i32 = -r;
+        let mut x2m1: i32 = -1;
+        let mut y: i32 = r;
+
+        for x in 0..=rpsqrt2 {
+            x2m1 += 2;
+            d += x2m1;
+
+            if d >= 0 {
+                y -= 1;
+                d -= y * 2;
+            }
+
+            for i in (sx - x)..=(ex + x) {
+                //                set_pixels(pixels, , pixcolor);
+                pixels[(sy - y * yd + i) as usize] = pixcolor;
+            }
+
+            for i in (sx - y)..=(ex + y) {
+                //                set_pixels(pixels, sy - x * yd + i, pixcolor);
+                pixels[(sy - x * yd + i) as usize] = pixcolor;
+            }
+
+            for i in (sx - y)..=(ex + y) {
+                //                set_pixels(pixels, ey + x * yd + i, pixcolor);
+                pixels[(ey + x * yd + i) as usize] = pixcolor;
+            }
+
+            for i in (sx - x)..=(ex + x) {
+                //                set_pixels(pixels, ey + y * yd + i, pixcolor);
+                pixels[(ey + y * yd + i) as usize] = pixcolor;
+            }
+        }
+    });
+}
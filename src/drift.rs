@@ -0,0 +1,100 @@
+use sdl2::rect::Point;
+
+/// Pixels the clock moves along each axis per shift.
+const STEP: i32 = 2;
+
+/// Moves the clock a few pixels at a time so that no pixel of an OLED or plasma
+/// panel shows the same thing for hours.
+///
+/// The offset travels diagonally and bounces off the edges of the range it may
+/// move in, so over time it covers all of it and never jumps far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    offset: Point,
+    direction: Point,
+}
+
+impl Default for Drift {
+    fn default() -> Drift {
+        Drift::new()
+    }
+}
+
+impl Drift {
+    /// Start at the layout position, heading right and down.
+    pub fn new() -> Drift {
+        Drift {
+            offset: Point::new(0, 0),
+            direction: Point::new(1, 1),
+        }
+    }
+
+    /// The current offset from the layout position.
+    pub fn offset(&self) -> Point {
+        self.offset
+    }
+
+    /// Move one step within `min..=max`, as returned by
+    /// [`FlipClockRenderer::offset_range`](crate::FlipClockRenderer::offset_range),
+    /// and return the new offset.
+    pub fn step(&mut self, (min, max): (Point, Point)) -> Point {
+        let (mut dx, mut dy) = (self.direction.x(), self.direction.y());
+        let x = advance(self.offset.x(), &mut dx, min.x(), max.x());
+        let y = advance(self.offset.y(), &mut dy, min.y(), max.y());
+        self.direction = Point::new(dx, dy);
+        self.offset = Point::new(x, y);
+        self.offset
+    }
+}
+
+/// Move `position` a step in `direction` within `min..=max`, turning around on
+/// reaching an edge.
+fn advance(position: i32, direction: &mut i32, min: i32, max: i32) -> i32 {
+    if min >= max {
+        return min;
+    }
+    let next = position + *direction * STEP;
+    if next <= min || next >= max {
+        *direction = -*direction;
+    }
+    next.clamp(min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(x: (i32, i32), y: (i32, i32)) -> (Point, Point) {
+        (Point::new(x.0, y.0), Point::new(x.1, y.1))
+    }
+
+    #[test]
+    fn bounces_off_the_edges() {
+        let mut drift = Drift::new();
+        let range = range((-1, 5), (0, 3));
+        let path: Vec<_> = (0..6)
+            .map(|_| {
+                let p = drift.step(range);
+                (p.x(), p.y())
+            })
+            .collect();
+        assert_eq!(path, [(2, 2), (4, 3), (5, 1), (3, 0), (1, 2), (-1, 3)]);
+    }
+
+    #[test]
+    fn stays_put_without_room() {
+        let mut drift = Drift::new();
+        assert_eq!(drift.step(range((0, 0), (-1, -1))), Point::new(0, -1));
+        assert_eq!(drift.step(range((0, 0), (-1, -1))), Point::new(0, -1));
+    }
+
+    #[test]
+    fn comes_back_into_a_smaller_range() {
+        let mut drift = Drift::new();
+        for _ in 0..3 {
+            drift.step(range((0, 10), (0, 10)));
+        }
+        assert_eq!(drift.offset(), Point::new(6, 6));
+        assert_eq!(drift.step(range((0, 2), (0, 2))), Point::new(2, 2));
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt;
+
+/// Errors reported by the clock and its frontend.
+#[derive(Debug)]
+pub enum GluqloError {
+    /// SDL or one of its subsystems (video, window, timer, SDL_ttf) failed to start.
+    Sdl(String),
+    /// A font could not be loaded.
+    Font(String),
+    /// Drawing, presenting or saving a frame failed.
+    Render(String),
+    /// Invalid command line options or configuration.
+    Config(String),
+}
+
+impl GluqloError {
+    /// Process exit code for this error, distinct per kind.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GluqloError::Config(_) => 2,
+            GluqloError::Sdl(_) => 3,
+            GluqloError::Font(_) => 4,
+            GluqloError::Render(_) => 5,
+        }
+    }
+}
+
+impl fmt::Display for GluqloError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GluqloError::Sdl(e) => write!(f, "SDL initialization failed: {}", e),
+            GluqloError::Font(e) => write!(f, "font error: {}", e),
+            GluqloError::Render(e) => write!(f, "rendering failed: {}", e),
+            GluqloError::Config(e) => write!(f, "invalid configuration: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GluqloError {}
+
+pub type Result<T> = std::result::Result<T, GluqloError>;
@@ -0,0 +1,194 @@
+use crate::{GluqloError, Result};
+use sdl2::rwops::RWops;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The Gluqlo font, compiled into the binary so the clock runs from any directory.
+pub const DEFAULT_FONT: &[u8] = include_bytes!("../gluqlo.ttf");
+
+/// File name looked up in the XDG data directories.
+const FONT_FILE: &str = "gluqlo.ttf";
+
+/// Where a font is loaded from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FontSource {
+    /// [`DEFAULT_FONT`].
+    #[default]
+    Embedded,
+    /// Face `index` of a TrueType/OpenType file or collection on disk.
+    File { path: PathBuf, index: u32 },
+}
+
+impl FontSource {
+    /// Pick the font to use.
+    ///
+    /// An explicit `path` wins, then a fontconfig `family` lookup, then
+    /// `gluqlo/gluqlo.ttf` in `$XDG_DATA_HOME` and `$XDG_DATA_DIRS`, and finally
+    /// the embedded font. A path or family that cannot be resolved is an error
+    /// rather than a silent fallback.
+    pub fn find(path: Option<&Path>, family: Option<&str>) -> Result<FontSource> {
+        if let Some(path) = path {
+            return FontSource::file(path, 0);
+        }
+
+        if let Some(family) = family {
+            return fontconfig_lookup(family).map(|path| FontSource::File { path, index: 0 });
+        }
+
+        Ok(xdg_data_font()
+            .map(|path| FontSource::File { path, index: 0 })
+            .unwrap_or(FontSource::Embedded))
+    }
+
+    /// Face `index` of the font file at `path`, which must exist.
+    pub fn file(path: &Path, index: u32) -> Result<FontSource> {
+        if !path.is_file() {
+            return Err(GluqloError::Font(format!(
+                "font file {} does not exist",
+                path.display()
+            )));
+        }
+        Ok(FontSource::File {
+            path: path.to_owned(),
+            index,
+        })
+    }
+
+    /// Open the font at `size` points.
+    pub fn load<'ttf>(
+        &self,
+        ttf_context: &'ttf Sdl2TtfContext,
+        size: u16,
+    ) -> Result<Font<'ttf, 'static>> {
+        let font = match self {
+            FontSource::Embedded => RWops::from_bytes(DEFAULT_FONT)
+                .and_then(|rwops| ttf_context.load_font_from_rwops(rwops, size)),
+            FontSource::File { path, index } => ttf_context.load_font_at_index(path, *index, size),
+        };
+        font.map_err(|e| GluqloError::Font(format!("cannot load {}: {}", self, e)))
+    }
+
+    /// Open the font at `size` points, or smaller if needed so that `measure`
+    /// stays within `max_width` x `max_height` pixels.
+    ///
+    /// The embedded font is hand-tuned for the card layout and always loaded at
+    /// `size`; fitting only applies to user fonts with different proportions.
+    pub fn load_fitted<'ttf, M>(
+        &self,
+        ttf_context: &'ttf Sdl2TtfContext,
+        size: u16,
+        max_width: u32,
+        max_height: u32,
+        measure: M,
+    ) -> Result<Font<'ttf, 'static>>
+    where
+        M: Fn(&Font) -> (u32, u32),
+    {
+        let mut size = size;
+        loop {
+            let font = self.load(ttf_context, size)?;
+            if *self == FontSource::Embedded || size <= 1 {
+                return Ok(font);
+            }
+
+            let (width, height) = measure(&font);
+            let ratio = (max_width as f32 / width.max(1) as f32)
+                .min(max_height as f32 / height.max(1) as f32);
+            if ratio >= 1. {
+                return Ok(font);
+            }
+            // Glyph sizes do not scale exactly with the point size, so always make progress.
+            size = ((size as f32 * ratio) as u16).clamp(1, size - 1);
+        }
+    }
+}
+
+impl fmt::Display for FontSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontSource::Embedded => write!(f, "embedded font"),
+            FontSource::File { path, index: 0 } => write!(f, "{}", path.display()),
+            FontSource::File { path, index } => write!(f, "{} (face {})", path.display(), index),
+        }
+    }
+}
+
+/// Ask fontconfig for the file of the best match for `family`.
+///
+/// `fc-match` always falls back to some font, so the match only counts if it
+/// is of the family asked for.
+fn fontconfig_lookup(family: &str) -> Result<PathBuf> {
+    let output = Command::new("fc-match")
+        .arg("--format=%{family}\n%{file}")
+        .arg(family)
+        .output()
+        .map_err(|e| GluqloError::Font(format!("cannot run fc-match: {}", e)))?;
+
+    let no_match = || GluqloError::Font(format!("fontconfig has no match for {:?}", family));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (families, file) = stdout.split_once('\n').ok_or_else(no_match)?;
+    if !output.status.success() || file.is_empty() {
+        return Err(no_match());
+    }
+    if !matches_family(family, families) {
+        return Err(GluqloError::Font(format!(
+            "fontconfig has no font of family {:?}, only {:?}",
+            family, families
+        )));
+    }
+    Ok(PathBuf::from(file))
+}
+
+/// Whether the fontconfig pattern `requested`, such as `DejaVu Sans:bold`,
+/// names one of the comma-separated `families` of a match. Like fontconfig,
+/// case and spaces don't matter.
+fn matches_family(requested: &str, families: &str) -> bool {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    let requested = normalize(requested.split(':').next().unwrap_or_default());
+    families
+        .split(',')
+        .any(|family| normalize(family) == requested)
+}
+
+/// `gluqlo/gluqlo.ttf` in the first XDG data directory that has one.
+fn xdg_data_font() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")));
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_owned());
+
+    data_home
+        .into_iter()
+        .chain(data_dirs.split(':').map(PathBuf::from))
+        .map(|dir| dir.join("gluqlo").join(FONT_FILE))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fontconfig_fallbacks_are_not_the_family_asked_for() {
+        assert!(matches_family("DejaVu Sans", "DejaVu Sans"));
+        assert!(matches_family("dejavusans:bold", "DejaVu Sans"));
+        assert!(matches_family(
+            "Noto Sans CJK JP",
+            "Noto Sans CJK JP,Noto Sans CJK JP Regular"
+        ));
+        assert!(!matches_family("No Such Font", "DejaVu Sans"));
+        assert!(!matches_family("DejaVu", "DejaVu Sans"));
+    }
+}
@@ -0,0 +1,33 @@
+//! Gluqlo flip clock rendering.
+//!
+//! [`FlipClockRenderer`] draws the split-flap clock onto any SDL surface, or
+//! through an SDL renderer on the GPU, and is independent of window creation
+//! and event handling, so it can be embedded in other SDL applications. The
+//! `gluqlo-rs` binary is a thin frontend over it.
+
+mod clock;
+mod draw;
+mod drift;
+mod error;
+mod font;
+mod night;
+mod paint;
+mod renderer;
+mod schedule;
+mod state;
+mod theme;
+mod transition;
+mod zone;
+
+pub use clock::{AcceleratedClock, ClockSource, FixedClock, ReplayClock, SystemClock};
+pub use drift::Drift;
+pub use error::{GluqloError, Result};
+pub use font::{FontSource, DEFAULT_FONT};
+pub use night::{NightMode, NightSchedule};
+pub use paint::{CacheStats, TextureCache};
+pub use renderer::{FlipClockRenderer, Options, Time};
+pub use schedule::Scheduler;
+pub use state::ClockState;
+pub use theme::{parse_color, Theme};
+pub use transition::Animation;
+pub use zone::Zone;
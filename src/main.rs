@@ -1,218 +1,1053 @@
-use sdl2::event::{Event, EventType};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use gluqlo::{
+    AcceleratedClock, Animation, CacheStats, ClockSource, ClockState, Drift, FlipClockRenderer,
+    FontSource, GluqloError, NightMode, NightSchedule, Options, Result, Scheduler, SystemClock,
+    TextureCache, Theme, Time, Zone,
+};
+use sdl2::event::{Event, EventType, WindowEvent};
+use sdl2::image::SaveSurface;
 use sdl2::keyboard::Keycode;
-use sdl2::pixels::{Color, PixelFormatEnum};
-use sdl2::rect::Rect;
-use sdl2::surface::{Surface, SurfaceRef};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Point;
+use sdl2::surface::Surface;
+use sdl2::sys::SDL_EventType;
+use sdl2::video::{Window, WindowContext};
 // use std::time::{Duration, Instant};
 // use time;
 use sdl2::{
-    gfx::rotozoom::RotozoomSurface, ttf::Font, ttf::Sdl2TtfContext, video::FullscreenType,
-    video::Window, EventPump, EventSubsystem, Sdl, TimerSubsystem,
+    ttf::Sdl2TtfContext, video::FullscreenType, EventPump, EventSubsystem, Sdl, TimerSubsystem,
+    VideoSubsystem,
 };
-use std::fmt::Write;
-use std::sync::atomic::{AtomicIsize, Ordering};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use structopt::StructOpt;
 // use std::cell::RefCell;
 
-const FONT: &str = "gluqlo.ttf";
+mod config;
+mod present;
+mod xscreensaver;
+
+use config::Config;
+use present::{FrameStats, Output, Presenter, Target};
+
 const TITLE: &str = "Gluqlo 1.1";
 
-const DEFAULT_A: u8 = 0xff;
-const FONT_COLOR: Color = Color {
-    r: 0xb7,
-    g: 0xb7,
-    b: 0xb7,
-    a: DEFAULT_A,
-};
-const BACKGROUND_COLOR: Color = Color {
-    r: 0x0f,
-    g: 0x0f,
-    b: 0x0f,
-    a: DEFAULT_A,
-};
+const DEFAULT_WIDTH: u32 = 1024;
+const DEFAULT_HEIGHT: u32 = 768;
+const DEFAULT_THEME: &str = "classic";
+const DEFAULT_FPS: u32 = 60;
+/// Format of the date line when `--date` is given without one, e.g. "Sat 18 Oct".
+const DEFAULT_DATE_FORMAT: &str = "%a %-d %b";
+
+/// How long a transition takes unless `--duration` says otherwise, in milliseconds.
+const DEFAULT_DURATION: u64 = 260;
 
-static PAST_H: AtomicIsize = AtomicIsize::new(-1);
-static PAST_M: AtomicIsize = AtomicIsize::new(-1);
+/// `code` of the user event that starts a flip.
+const FLIP_EVENT: i32 = 0;
+/// `code` of the user event that redraws everything after the clock jumped.
+const RESYNC_EVENT: i32 = 1;
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "basic")]
 struct Opt {
-    #[structopt(short, long, default_value = "1024")]
-    width: u32,
+    /// Configuration file to read instead of $XDG_CONFIG_HOME/gluqlo/config.toml
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// Window width [default: 1024]
+    #[structopt(short, long)]
+    width: Option<u32>,
 
-    #[structopt(short, long, default_value = "768")]
-    height: u32,
+    /// Window height [default: 768]
+    #[structopt(short, long)]
+    height: Option<u32>,
 
-    #[structopt(short = "s", long, default_value = "1.")]
-    display_scale_factor: f32,
+    /// Fraction of the window used by the clock [default: 1]
+    #[structopt(short = "s", long)]
+    display_scale_factor: Option<f32>,
 
     #[structopt(long = "ampm")]
     ampm: bool,
 
+    /// Show a 24-hour clock even if the configuration asks for AM/PM
+    #[structopt(long = "24h", conflicts_with = "ampm")]
+    h24: bool,
+
     #[structopt(long = "leardingzero")]
     leadingzero: bool,
 
+    /// Do not pad single-digit hours, overriding the configuration
+    #[structopt(long = "no-leadingzero", conflicts_with = "leadingzero")]
+    no_leadingzero: bool,
+
     #[structopt(short, long)]
     fullscreen: bool,
+
+    /// Show the seconds on a third card
+    #[structopt(long)]
+    seconds: bool,
+
+    /// Show the date below the cards, written with the strftime FORMAT
+    /// [default: "%a %-d %b"]
+    #[structopt(long, value_name = "FORMAT")]
+    date: Option<Option<String>>,
+
+    /// Run in a window even if the configuration asks for fullscreen
+    #[structopt(long, conflicts_with = "fullscreen")]
+    windowed: bool,
+
+    /// Open the window on this display, counting from 0
+    #[structopt(long, conflicts_with_all = &["all-displays", "root", "window-id"])]
+    display: Option<i32>,
+
+    /// Open a fullscreen clock on every display, each laid out for its own size
+    #[structopt(long = "all-displays", conflicts_with_all = &["root", "window-id"])]
+    all_displays: bool,
+
+    /// With --all-displays, show every clock on each display (mirror) or deal the
+    /// --world zones out over the displays (spread) [default: mirror]
+    #[structopt(long = "per-display", possible_values = &["mirror", "spread"])]
+    per_display: Option<PerDisplay>,
+
+    /// Font file to use instead of the embedded font
+    #[structopt(long, parse(from_os_str))]
+    font: Option<PathBuf>,
+
+    /// Font family to look up with fontconfig
+    #[structopt(long = "font-family", conflicts_with = "font")]
+    font_family: Option<String>,
+
+    /// Font for the digits as PATH[:INDEX], INDEX selecting a face of a collection
+    #[structopt(long = "digit-font")]
+    digit_font: Option<FontArg>,
+
+    /// Font for the AM/PM label as PATH[:INDEX]
+    #[structopt(long = "label-font")]
+    label_font: Option<FontArg>,
+
+    /// Built-in theme (classic, light, amber, high-contrast) or path to a TOML theme file
+    /// [default: classic]
+    #[structopt(long)]
+    theme: Option<String>,
+
+    /// Override one theme color as KEY=#RRGGBB[AA], e.g. --color digits=#ffb000
+    #[structopt(long = "color", number_of_values = 1)]
+    colors: Vec<String>,
+
+    /// Fade to --night-theme at night: from dusk to dawn given as HH:MM-HH:MM, or
+    /// from sunset to sunrise at --latitude and --longitude with "sun"
+    #[structopt(long, value_name = "HH:MM-HH:MM|sun")]
+    night: Option<String>,
+
+    /// Latitude for --night sun, in degrees north
+    #[structopt(long, allow_hyphen_values = true)]
+    latitude: Option<f64>,
+
+    /// Longitude for --night sun, in degrees east
+    #[structopt(long, allow_hyphen_values = true)]
+    longitude: Option<f64>,
+
+    /// Built-in theme or theme file to fade to at night [default: night]
+    #[structopt(long = "night-theme")]
+    night_theme: Option<String>,
+
+    /// Brightness of the night theme, from 0 (black) to 1 [default: 1]
+    #[structopt(long = "night-brightness")]
+    night_brightness: Option<f32>,
+
+    /// Time zone to show, e.g. Europe/Berlin [default: $TZ or the system zone]
+    #[structopt(long, conflicts_with = "world")]
+    tz: Option<String>,
+
+    /// Show a world clock with one captioned clock per ZONE[=CAPTION], e.g.
+    /// --world Asia/Tokyo --world America/New_York=NYC
+    #[structopt(long, number_of_values = 1)]
+    world: Vec<String>,
+
+    /// Draw on the whole screen, into $XSCREENSAVER_WINDOW when set (XScreenSaver `-root`)
+    #[structopt(long)]
+    root: bool,
+
+    /// Draw into the existing X window with this id (XScreenSaver `-window-id`)
+    #[structopt(long = "window-id", parse(try_from_str = xscreensaver::parse_xid))]
+    window_id: Option<u64>,
+
+    /// Move the clock by a few pixels within the margin left by --display-scale-factor,
+    /// against burn-in: before every flip, or every N minutes
+    #[structopt(long, value_name = "flip|N")]
+    shift: Option<ShiftEvery>,
+
+    /// Frames per second to draw flips at [default: 60]
+    #[structopt(long)]
+    fps: Option<u32>,
+
+    /// How cards turn over: flip, flip3d for a perspective fold, slide (or roll),
+    /// crossfade, or none [default: flip]
+    #[structopt(long, alias = "animation", possible_values = Animation::NAMES)]
+    transition: Option<Animation>,
+
+    /// How long a transition takes, in milliseconds [default: 260]
+    #[structopt(long)]
+    duration: Option<u64>,
+
+    /// Present frames through an SDL renderer in step with the display refresh
+    #[structopt(long)]
+    vsync: bool,
+
+    /// Draw with the CPU (software) or with the GPU through an SDL renderer
+    /// (accelerated), which falls back to software without a GPU [default: software]
+    #[structopt(long, possible_values = &["software", "accelerated"])]
+    renderer: Option<Backend>,
+
+    /// Print the frame timing of every flip and show glyph cache statistics on screen
+    #[structopt(long)]
+    debug: bool,
+
+    /// Run the clock this many times as fast as real time, e.g. 60 for a minute per second
+    #[structopt(long)]
+    speed: Option<f64>,
+
+    /// Start the clock at this time as HH:MM[:SS] in --tz instead of now
+    #[structopt(long, conflicts_with = "render-png")]
+    start: Option<String>,
+
+    /// Render a single frame offscreen, save it as PNG and exit
+    #[structopt(long = "render-png", parse(from_os_str))]
+    render_png: Option<PathBuf>,
+
+    /// Time to render in headless mode, as HH:MM or HH:MM:SS in --tz (defaults to now)
+    #[structopt(long, requires = "render-png")]
+    at: Option<String>,
+}
+
+/// A font file with an optional face index, written as `PATH[:INDEX]`.
+#[derive(Debug)]
+struct FontArg {
+    path: PathBuf,
+    index: u32,
+}
+
+impl FromStr for FontArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<FontArg, String> {
+        // Only a numeric suffix is an index, so `C:\fonts\a.ttc` stays a path.
+        if let Some((path, index)) = s.rsplit_once(':') {
+            if !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()) {
+                let index = index
+                    .parse()
+                    .map_err(|e| format!("invalid face index {:?}: {}", index, e))?;
+                return Ok(FontArg {
+                    path: PathBuf::from(path),
+                    index,
+                });
+            }
+        }
+        Ok(FontArg {
+            path: PathBuf::from(s),
+            index: 0,
+        })
+    }
+}
+
+/// What draws the frames, picked with `--renderer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Backend {
+    /// SDL blits and SDL_gfx zooming into a surface.
+    #[default]
+    Software,
+    /// Textures drawn by an accelerated SDL renderer.
+    Accelerated,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Backend, String> {
+        match s {
+            "software" => Ok(Backend::Software),
+            "accelerated" => Ok(Backend::Accelerated),
+            _ => Err(format!(
+                "unknown renderer {:?}, expected software or accelerated",
+                s
+            )),
+        }
+    }
+}
+
+/// What each display shows with `--all-displays`, picked with `--per-display`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum PerDisplay {
+    /// Every display shows every clock.
+    #[default]
+    Mirror,
+    /// The `--world` zones are dealt out over the displays.
+    Spread,
+}
+
+impl FromStr for PerDisplay {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<PerDisplay, String> {
+        match s {
+            "mirror" => Ok(PerDisplay::Mirror),
+            "spread" => Ok(PerDisplay::Spread),
+            _ => Err(format!(
+                "unknown per-display mode {:?}, expected mirror or spread",
+                s
+            )),
+        }
+    }
+}
+
+/// How often `--shift` moves the clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShiftEvery {
+    /// Before every flip.
+    Flip,
+    /// Before the first flip after this many minutes.
+    Minutes(u32),
+}
+
+impl ShiftEvery {
+    /// Whether the clock is due to move, `since` the last time it did.
+    fn due(self, since: Instant) -> bool {
+        match self {
+            ShiftEvery::Flip => true,
+            ShiftEvery::Minutes(minutes) => {
+                since.elapsed() >= Duration::from_secs(60 * u64::from(minutes))
+            }
+        }
+    }
+}
+
+impl FromStr for ShiftEvery {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<ShiftEvery, String> {
+        match s {
+            "flip" => Ok(ShiftEvery::Flip),
+            _ => match s.parse() {
+                Ok(minutes) if minutes > 0 => Ok(ShiftEvery::Minutes(minutes)),
+                _ => Err(format!(
+                    "invalid shift {:?}, expected flip or a number of minutes",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+fn parse_font_arg(s: &str) -> Result<FontArg> {
+    s.parse().map_err(GluqloError::Config)
+}
+
+impl FontArg {
+    fn source(&self) -> Result<FontSource> {
+        FontSource::file(&self.path, self.index)
+    }
+}
+
+impl Opt {
+    /// Fill in everything not given on the command line from `config`.
+    fn merge(&mut self, config: Config) -> Result<()> {
+        self.width = self.width.or(config.width);
+        self.height = self.height.or(config.height);
+        self.display_scale_factor = self.display_scale_factor.or(config.scale);
+
+        self.ampm = self.ampm || (!self.h24 && config.ampm == Some(true));
+        self.leadingzero =
+            self.leadingzero || (!self.no_leadingzero && config.leading_zero == Some(true));
+        self.fullscreen = self.fullscreen || (!self.windowed && config.fullscreen == Some(true));
+        self.seconds = self.seconds || config.seconds == Some(true);
+        if self.date.is_none() {
+            self.date = config.date.map(Some);
+        }
+        // Either way of picking displays on the command line replaces both in the file.
+        if self.display.is_none() && !self.all_displays {
+            self.display = config.display;
+            self.all_displays = config.all_displays == Some(true);
+        }
+        if self.per_display.is_none() {
+            self.per_display = config
+                .per_display
+                .as_deref()
+                .map(|s| s.parse().map_err(GluqloError::Config))
+                .transpose()?;
+        }
+        self.fps = self.fps.or(config.fps);
+        self.vsync = self.vsync || config.vsync == Some(true);
+        if self.transition.is_none() {
+            self.transition = config.transition.as_deref().map(str::parse).transpose()?;
+        }
+        self.duration = self.duration.or(config.duration);
+        if self.shift.is_none() {
+            self.shift = config
+                .shift
+                .as_deref()
+                .map(|s| s.parse().map_err(GluqloError::Config))
+                .transpose()?;
+        }
+        if self.renderer.is_none() {
+            self.renderer = config
+                .renderer
+                .as_deref()
+                .map(|s| s.parse().map_err(GluqloError::Config))
+                .transpose()?;
+        }
+
+        // A font picked on the command line replaces both ways of picking one in the file.
+        if self.font.is_none() && self.font_family.is_none() {
+            self.font = config.font;
+            self.font_family = config.font_family;
+        }
+        if self.digit_font.is_none() {
+            self.digit_font = config
+                .digit_font
+                .as_deref()
+                .map(parse_font_arg)
+                .transpose()?;
+        }
+        if self.label_font.is_none() {
+            self.label_font = config
+                .label_font
+                .as_deref()
+                .map(parse_font_arg)
+                .transpose()?;
+        }
+
+        // Either way of picking zones on the command line replaces both in the file.
+        if self.tz.is_none() && self.world.is_empty() {
+            self.tz = config.tz;
+            self.world = config.world;
+        }
+
+        self.theme = self.theme.take().or(config.theme);
+        self.night = self.night.take().or(config.night);
+        self.latitude = self.latitude.or(config.latitude);
+        self.longitude = self.longitude.or(config.longitude);
+        self.night_theme = self.night_theme.take().or(config.night_theme);
+        self.night_brightness = self.night_brightness.or(config.night_brightness);
+        // Command line colors are applied last so they win.
+        let colors = config
+            .colors
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, value));
+        self.colors = colors.chain(self.colors.drain(..)).collect();
+
+        Ok(())
+    }
+
+    fn width(&self) -> u32 {
+        self.width.unwrap_or(DEFAULT_WIDTH)
+    }
+
+    fn height(&self) -> u32 {
+        self.height.unwrap_or(DEFAULT_HEIGHT)
+    }
+
+    fn fps(&self) -> Result<u32> {
+        match self.fps.unwrap_or(DEFAULT_FPS) {
+            0 => Err(GluqloError::Config(
+                "invalid --fps 0, expected at least 1".to_owned(),
+            )),
+            fps => Ok(fps),
+        }
+    }
+
+    fn display_scale_factor(&self) -> Result<f32> {
+        match self.display_scale_factor.unwrap_or(1.) {
+            scale if scale > 0. && scale <= 1. => Ok(scale),
+            scale => Err(GluqloError::Config(format!(
+                "invalid --display-scale-factor {}, expected more than 0 and at most 1",
+                scale
+            ))),
+        }
+    }
+
+    fn duration(&self) -> Result<Duration> {
+        match self.duration.unwrap_or(DEFAULT_DURATION) {
+            0 => Err(GluqloError::Config(
+                "invalid --duration 0, expected at least 1 ms; use --transition none for no animation"
+                    .to_owned(),
+            )),
+            ms => Ok(Duration::from_millis(ms)),
+        }
+    }
+
+    /// The format of the date line, if there is one.
+    fn date_format(&self) -> Result<Option<String>> {
+        let format = match &self.date {
+            Some(format) => format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT),
+            None => return Ok(None),
+        };
+        // A format chrono cannot write, or one asking for the time, only fails
+        // when it is written, so try it on a date now.
+        let mut sample = String::new();
+        let day = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap_or_default();
+        std::fmt::write(&mut sample, format_args!("{}", day.format(format)))
+            .map_err(|_| GluqloError::Config(format!("invalid --date format {:?}", format)))?;
+        if sample.is_empty() {
+            return Err(GluqloError::Config(format!(
+                "--date format {:?} writes nothing",
+                format
+            )));
+        }
+        Ok(Some(format.to_owned()))
+    }
+
+    fn options(&self) -> Result<Options> {
+        let font = FontSource::find(self.font.as_deref(), self.font_family.as_deref())?;
+        let digit_font = match &self.digit_font {
+            Some(arg) => arg.source()?,
+            None => font.clone(),
+        };
+        let label_font = match &self.label_font {
+            Some(arg) => arg.source()?,
+            None => font,
+        };
+
+        let mut theme = Theme::load(self.theme.as_deref().unwrap_or(DEFAULT_THEME))?;
+        for color in &self.colors {
+            let (key, value) = color.split_once('=').ok_or_else(|| {
+                GluqloError::Config(format!("invalid --color {:?}, expected KEY=#RRGGBB", color))
+            })?;
+            theme.set(key, value)?;
+        }
+
+        Ok(Options {
+            ampm: self.ampm,
+            leading_zero: self.leadingzero,
+            display_scale_factor: self.display_scale_factor()?,
+            animation: self.transition.unwrap_or_default(),
+            seconds: self.seconds,
+            digit_font,
+            label_font,
+            theme,
+            caption: None,
+            date: self.date_format()?,
+        })
+    }
+
+    /// The night mode fading from `day` to the night theme, if `--night` asks for one.
+    fn night_mode(&self, day: Theme) -> Result<Option<NightMode>> {
+        let schedule = match self.night.as_deref() {
+            None => return Ok(None),
+            Some("sun") => match (self.latitude, self.longitude) {
+                (Some(latitude), Some(longitude))
+                    if (-90. ..=90.).contains(&latitude)
+                        && (-180. ..=180.).contains(&longitude) =>
+                {
+                    NightSchedule::Sun {
+                        latitude,
+                        longitude,
+                    }
+                }
+                _ => return Err(GluqloError::Config(
+                    "--night sun needs --latitude from -90 to 90 and --longitude from -180 to 180"
+                        .to_owned(),
+                )),
+            },
+            Some(hours) => hours.parse()?,
+        };
+
+        let brightness = self.night_brightness.unwrap_or(1.);
+        if !(0. ..=1.).contains(&brightness) {
+            return Err(GluqloError::Config(format!(
+                "invalid --night-brightness {}, expected 0 to 1",
+                brightness
+            )));
+        }
+        Ok(Some(NightMode {
+            schedule,
+            day,
+            night: Theme::load(self.night_theme.as_deref().unwrap_or("night"))?,
+            brightness,
+        }))
+    }
+
+    /// Where the clocks get the time from: the wall clock, or a simulated one
+    /// for `--speed` and `--start`.
+    fn clock_source(&self) -> Result<Arc<dyn ClockSource>> {
+        if self.speed.is_none() && self.start.is_none() {
+            return Ok(Arc::new(SystemClock));
+        }
+
+        let speed = self.speed.unwrap_or(1.);
+        if !(speed.is_finite() && speed > 0.) {
+            return Err(GluqloError::Config(format!(
+                "invalid --speed {}, expected a positive number",
+                speed
+            )));
+        }
+        let now = SystemClock.now();
+        let start = match &self.start {
+            Some(start) => self
+                .zone()?
+                .instant_on_day(now, parse_time(start, "--start")?)?,
+            None => now,
+        };
+        Ok(Arc::new(AcceleratedClock::new(start, speed)))
+    }
+
+    /// The zone of the single clock and of `--at`.
+    fn zone(&self) -> Result<Zone> {
+        match &self.tz {
+            Some(name) => Zone::named(name),
+            None => Ok(Zone::local()),
+        }
+    }
+
+    /// Every clock to show with its caption; captions are only used by `--world`.
+    fn zones(&self) -> Result<Vec<(Zone, Option<String>)>> {
+        if self.world.is_empty() {
+            return Ok(vec![(self.zone()?, None)]);
+        }
+        self.world
+            .iter()
+            .map(|entry| {
+                let (name, caption) = match entry.split_once('=') {
+                    Some((name, caption)) => (name, Some(caption.to_owned())),
+                    None => (entry.as_str(), None),
+                };
+                let zone = Zone::named(name)?;
+                let caption = caption.unwrap_or_else(|| zone.city());
+                Ok((zone, Some(caption)))
+            })
+            .collect()
+    }
+
+    /// The clocks to show on display `index` of `count`: all of them, or with
+    /// `--per-display spread` every `count`th one. With more displays than
+    /// clocks, the clocks repeat.
+    fn zones_on(&self, index: usize, count: usize) -> Result<Vec<(Zone, Option<String>)>> {
+        let zones = self.zones()?;
+        if self.per_display.unwrap_or_default() == PerDisplay::Mirror || count <= 1 {
+            return Ok(zones);
+        }
+        if zones.len() < count {
+            return Ok(vec![zones[index % zones.len()].clone()]);
+        }
+        Ok(zones
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| i % count == index)
+            .map(|(_, zone)| zone)
+            .collect())
+    }
+
+    /// The displays to open a window on, `None` leaving the choice to SDL.
+    fn displays(&self, video_subsystem: &VideoSubsystem) -> Result<Vec<Option<i32>>> {
+        let count = video_subsystem
+            .num_video_displays()
+            .map_err(GluqloError::Sdl)?;
+        if self.all_displays {
+            return Ok((0..count).map(Some).collect());
+        }
+        match self.display {
+            Some(display) if !(0..count).contains(&display) => Err(GluqloError::Config(format!(
+                "invalid --display {}, expected 0 to {}",
+                display,
+                count - 1
+            ))),
+            display => Ok(vec![display]),
+        }
+    }
+}
+
+/// Open a `opt.width()` x `opt.height()` window, centered on `display` if given.
+fn open_window(
+    video_subsystem: &VideoSubsystem,
+    opt: &Opt,
+    display: Option<i32>,
+) -> Result<Window> {
+    let (w, h) = (opt.width(), opt.height());
+    let mut builder = video_subsystem.window(TITLE, w, h);
+    builder.allow_highdpi();
+    if let Some(display) = display {
+        let bounds = video_subsystem
+            .display_bounds(display)
+            .map_err(GluqloError::Sdl)?;
+        builder.position(
+            bounds.x() + (bounds.width() as i32 - w as i32) / 2,
+            bounds.y() + (bounds.height() as i32 - h as i32) / 2,
+        );
+    }
+    builder.build().map_err(|e| GluqloError::Sdl(e.to_string()))
+}
+
+/// One clock on screen: the zone it shows, where, and what it shows now.
+struct ZoneClock<'a> {
+    zone: Zone,
+    renderer: FlipClockRenderer<'a>,
+    state: Arc<ClockState>,
+}
+
+/// Lay out a clock for each of `zones` on a `w` x `h` target, in a grid if there
+/// are several.
+fn zone_clocks<'a>(
+    ttf_context: &'a Sdl2TtfContext,
+    opt: &Opt,
+    zones: Vec<(Zone, Option<String>)>,
+    w: u32,
+    h: u32,
+) -> Result<Vec<ZoneClock<'a>>> {
+    let options = opt.options()?;
+    let viewports = FlipClockRenderer::grid(&options, zones.len() as u32, w, h);
+
+    zones
+        .into_iter()
+        .zip(viewports)
+        .map(|((zone, caption), viewport)| {
+            let options = Options {
+                caption,
+                ..options.clone()
+            };
+            Ok(ZoneClock {
+                zone,
+                renderer: FlipClockRenderer::with_viewport(ttf_context, viewport, options)?,
+                state: Arc::new(ClockState::new()),
+            })
+        })
+        .collect()
+}
+
+/// Whether `displayed` already shows `time`, down to the second with the seconds card.
+fn shows(displayed: Option<Time>, time: Time, seconds: bool) -> bool {
+    displayed.is_some_and(|d| {
+        d.hour == time.hour && d.minute == time.minute && (!seconds || d.second == time.second)
+    })
+}
+
+/// A window and the clocks laid out in it.
+struct Screen<'a> {
+    presenter: Presenter<'a>,
+    /// Card and glyph textures of the clocks, when drawn by an accelerated renderer.
+    textures: Option<TextureCache<'a, WindowContext>>,
+    clocks: Vec<ZoneClock<'a>>,
+    /// Window size and display the clocks are laid out for.
+    size: (u32, u32),
+    display: Option<i32>,
+}
+
+impl<'a> Screen<'a> {
+    fn new(presenter: Presenter<'a>, clocks: Vec<ZoneClock<'a>>) -> Screen<'a> {
+        let window = presenter.window();
+        let size = window.size();
+        let display = window.display_index().ok();
+        Screen {
+            textures: presenter.texture_creator().map(TextureCache::new),
+            presenter,
+            clocks,
+            size,
+            display,
+        }
+    }
+
+    /// Whether this screen is the window `window_id`, or any window for `None`.
+    fn is(&self, window_id: Option<u32>) -> bool {
+        window_id.is_none_or(|id| id == self.presenter.window().id())
+    }
+
+    /// Lay the clocks out again for the current size and display of the window.
+    /// Returns whether either changed.
+    fn relayout(&mut self, ttf_context: &'a Sdl2TtfContext) -> Result<bool> {
+        let window = self.presenter.window();
+        let size = window.size();
+        let display = window.display_index().ok();
+        if (size, display) == (self.size, self.display) {
+            return Ok(false);
+        }
+
+        let (w, h) = size;
+        let options = self.clocks[0].renderer.options().clone();
+        let viewports = FlipClockRenderer::grid(&options, self.clocks.len() as u32, w, h);
+        for (clock, viewport) in self.clocks.iter_mut().zip(viewports) {
+            clock.renderer.relayout(ttf_context, viewport)?;
+        }
+        self.presenter.resize()?;
+        self.size = size;
+        self.display = display;
+        Ok(true)
+    }
+
+    /// Draw every clock from scratch at `now`.
+    fn redraw(&mut self, event_pump: &EventPump, now: DateTime<Utc>, debug: bool) -> Result<()> {
+        let renderer = &self.clocks[0].renderer;
+        self.presenter
+            .present(event_pump, |target| clear(renderer, target))?;
+        for clock in &self.clocks {
+            clock.state.reset();
+        }
+        self.render(event_pump, now, 20, 19, debug)
+    }
+
+    /// Draw `step` of the flip to the time at `now` on every clock.
+    fn render(
+        &mut self,
+        event_pump: &EventPump,
+        now: DateTime<Utc>,
+        maxsteps: i32,
+        step: i32,
+        debug: bool,
+    ) -> Result<()> {
+        let clocks = &self.clocks;
+        let textures = &mut self.textures;
+        let mut times = Vec::with_capacity(clocks.len());
+        self.presenter.present(event_pump, |mut target| {
+            for clock in clocks {
+                let time = clock.zone.time_at(now)?;
+                let previous = clock.state.displayed();
+                match &mut target {
+                    Target::Surface(surface) => clock
+                        .renderer
+                        .render(surface, time, previous, maxsteps, step)?,
+                    Target::Canvas(canvas) => clock.renderer.render_canvas(
+                        canvas,
+                        textures
+                            .as_mut()
+                            .expect("accelerated screens have textures"),
+                        time,
+                        previous,
+                        maxsteps,
+                        step,
+                    )?,
+                }
+                times.push(time);
+            }
+
+            if debug {
+                let stats = clocks
+                    .iter()
+                    .map(|clock| clock.renderer.cache_stats())
+                    .chain(textures.iter().map(TextureCache::stats))
+                    .fold(CacheStats::default(), |sum, stats| sum + stats);
+                let overlay = stats.to_string();
+                let renderer = &clocks[0].renderer;
+                match target {
+                    Target::Surface(surface) => renderer.render_overlay(surface, &overlay)?,
+                    Target::Canvas(canvas) => renderer.render_overlay_canvas(
+                        canvas,
+                        textures
+                            .as_mut()
+                            .expect("accelerated screens have textures"),
+                        &overlay,
+                    )?,
+                }
+            }
+            Ok(())
+        })?;
+
+        if step == maxsteps - 1 {
+            for (clock, time) in self.clocks.iter().zip(times) {
+                clock.state.set_displayed(time);
+            }
+        }
+        Ok(())
+    }
 }
 
 struct ScreenSaver<'a> {
-    window: Window,
+    ttf_context: &'a Sdl2TtfContext,
+    /// One window per display with `--all-displays`, otherwise just one.
+    screens: Vec<Screen<'a>>,
     event_pump: EventPump,
-    hour_background: Rect,
-    min_background: Rect,
-    // bgrect: Rect,
-    bg: Surface<'a>,
-    font_time: Font<'a, 'a>,
-    font_mode: Font<'a, 'a>,
-    opt: &'a Opt,
-    // past_h: RefCell<i32>,
-    // past_m: RefCell<i32>,
-    //    radius: i32,
-    animate: bool,
+    clock_source: Arc<dyn ClockSource>,
+    scheduler: Scheduler,
+    fps: u32,
+    duration: Duration,
+    /// How often the clocks move against burn-in, how, and when they last did.
+    shift: Option<ShiftEvery>,
+    drift: Drift,
+    shifted: Instant,
+    /// Fades the theme at night, on the wall clock of `zone`.
+    night: Option<NightMode>,
+    zone: Zone,
+    debug: bool,
     time_subsystem: TimerSubsystem,
     event_subsystem: EventSubsystem,
     // mouse_util: MouseUtil,
 }
 
 impl<'a> ScreenSaver<'a> {
+    /// Show clocks in `outputs`, as [`open_outputs`] made them for `opt`.
     pub fn new(
         sdl_context: &Sdl,
         ttf_context: &'a Sdl2TtfContext,
-        opt: &'a Opt,
-    ) -> ScreenSaver<'a> {
-        let mut width = opt.width;
-        let mut height = opt.height;
-        let video_subsystem = sdl_context.video().unwrap();
-
-        let mut window = video_subsystem
-            .window(TITLE, width, height)
-            .allow_highdpi()
-            .build()
-            .unwrap();
-
-        if opt.fullscreen {
-            window.set_fullscreen(FullscreenType::Desktop).unwrap();
-            sdl_context.mouse().show_cursor(false);
-        }
-        let event_pump = sdl_context.event_pump().unwrap();
-
-        let (w, h) = window.size();
-        width = (w as f32 * opt.display_scale_factor) as u32;
-        height = (h as f32 * opt.display_scale_factor) as u32;
-
-        let font_time = ttf_context
-            .load_font(FONT, (height as f32 / 1.68) as u16)
-            .unwrap();
-        let font_mode = ttf_context
-            .load_font(FONT, (height as f32 / 16.5) as u16)
-            .unwrap();
-
-        let mut screen = window.surface(&event_pump).unwrap();
-        screen.fill_rect(None, Color::RGB(0, 0, 0)).unwrap();
-        screen.finish().unwrap();
-
-        let rectsize = (height as f32 * 0.6) as u32;
-        let spacing = (width as f32 * 0.031) as i32;
-        let radius = (height as f32 * 0.05714) as i32;
-
-        let mut jitter_width: i32 = 1;
-        let mut jitter_height: i32 = 1;
-
-        if (opt.display_scale_factor - 1.) > ::std::f32::EPSILON {
-            jitter_width = ((w - width) as f32 * 0.5) as i32;
-            jitter_height = ((h - height) as f32 * 0.5) as i32;
-        }
-
-        let hour_background = Rect::new(
-            (0.5 * (width as f32 - (0.031 * width as f32) - (1.2 * height as f32))) as i32
-                + jitter_width,
-            (0.2 * height as f32) as i32 + jitter_height,
-            rectsize,
-            rectsize,
-        );
-
-        let min_background = Rect::new(
-            hour_background.x() + (0.6 * height as f32) as i32 + spacing,
-            hour_background.y(),
-            rectsize,
-            rectsize,
-        );
-
-        let bgrect = Rect::new(0, 0, rectsize, rectsize);
+        outputs: &'a mut [Output],
+        opt: &Opt,
+    ) -> Result<ScreenSaver<'a>> {
+        let event_pump = sdl_context.event_pump().map_err(GluqloError::Sdl)?;
+
+        let count = outputs.len();
+        let mut screens = Vec::with_capacity(count);
+        for (index, output) in outputs.iter_mut().enumerate() {
+            let mut presenter = Presenter::new(output)?;
+            let (w, h) = presenter.window().size();
+            let clocks = zone_clocks(ttf_context, opt, opt.zones_on(index, count)?, w, h)?;
+            presenter.present(&event_pump, |target| clear(&clocks[0].renderer, target))?;
+            screens.push(Screen::new(presenter, clocks));
+        }
 
-        // dbg!(PixelFormatEnum::RGB24.into_masks());
-        let mut bg = Surface::new(rectsize, rectsize, PixelFormatEnum::RGBA32).unwrap();
-        fill_rounded_box_b(&mut bg, &bgrect, radius, BACKGROUND_COLOR);
+        let options = screens[0].clocks[0].renderer.options();
+        let duration = opt.duration()?;
+        let clock_source = opt.clock_source()?;
+        // The scheduler works in clock time, which runs `speed` times as fast
+        // as the animation. It never leads by more than a period anyway.
+        let lead = if options.animation != Animation::Instant {
+            Duration::from_secs_f64((duration.as_secs_f64() * clock_source.speed()).min(60.))
+        } else {
+            Duration::ZERO
+        };
+        let scheduler = Scheduler::for_clock(options.seconds, lead);
+        let night = opt.night_mode(options.theme)?;
 
-        let time_subsystem = sdl_context.timer().unwrap();
-        let event_subsystem = sdl_context.event().unwrap();
+        let time_subsystem = sdl_context.timer().map_err(GluqloError::Sdl)?;
+        let event_subsystem = sdl_context.event().map_err(GluqloError::Sdl)?;
 
-        ScreenSaver {
-            window,
+        Ok(ScreenSaver {
+            ttf_context,
+            screens,
             event_pump,
-            hour_background,
-            min_background,
-            // bgrect,
-            bg,
-            font_time,
-            font_mode,
-            opt,
-            // past_h: RefCell::new(-1),
-            // past_m: RefCell::new(-1),
-            //            radius,
-            animate: true,
+            clock_source,
+            scheduler,
+            fps: opt.fps()?,
+            duration,
+            shift: opt.shift,
+            drift: Drift::new(),
+            shifted: Instant::now(),
+            night,
+            zone: opt.zone()?,
+            debug: opt.debug,
             time_subsystem,
             event_subsystem,
-        }
+        })
+    }
+
+    /// The first clock, whose options every clock shares.
+    fn options(&self) -> &Options {
+        self.screens[0].clocks[0].renderer.options()
     }
 
-    pub fn run(&mut self) {
-        self.render_clock(20, 19);
-        let event_subsystem = &self.event_subsystem;
-        let _timer = self.time_subsystem.add_timer(
-            60,
+    pub fn run(&mut self) -> Result<()> {
+        self.update_theme(self.clock_source.now())?;
+        self.redraw(None)?;
+        let event_subsystem = self.event_subsystem.clone();
+        let time_subsystem = self.time_subsystem.clone();
+        let clock_source = Arc::clone(&self.clock_source);
+        let mut scheduler = self.scheduler.clone();
+        let watched: Vec<_> = self
+            .screens
+            .iter()
+            .flat_map(|screen| &screen.clocks)
+            .map(|clock| (clock.zone.clone(), Arc::clone(&clock.state)))
+            .collect();
+        let seconds = self.options().seconds;
+        let _timer = time_subsystem.add_timer(
+            1,
             Box::new(move || {
-                let time_i = time::now();
+                let now = clock_source.now();
+                let speed = clock_source.speed();
 
-                let interval = if time_i.tm_min != PAST_M.load(Ordering::Relaxed) as i32 {
+                let code = if scheduler.jumped(Instant::now(), now, speed) {
+                    Some(RESYNC_EVENT)
+                } else {
+                    // With the seconds card every second is a flip, otherwise only the minute.
+                    let target = scheduler.target(now);
+                    let changed = watched.iter().any(|(zone, state)| {
+                        zone.time_at(target)
+                            .is_ok_and(|time| !shows(state.displayed(), time, seconds))
+                    });
+                    if changed {
+                        Some(FLIP_EVENT)
+                    } else {
+                        None
+                    }
+                };
+
+                if let Some(code) = code {
                     let e = Event::User {
                         type_: EventType::User as u32,
-                        code: 0,
+                        code,
                         data1: std::ptr::null_mut(),
                         data2: std::ptr::null_mut(),
                         window_id: 0,
                         timestamp: 0,
                     };
-                    event_subsystem.push_event(e).unwrap();
-                    // println!("push event");
-                    (1000 * (60 - time_i.tm_sec) - 250) as u32
-                } else {
-                    250
-                };
-                interval
+                    // Nothing to propagate to from the timer thread; a full event
+                    // queue only delays the flip until the next wake-up.
+                    if let Err(e) = event_subsystem.push_event(e) {
+                        eprintln!("gluqlo: cannot queue redraw: {}", e);
+                    }
+                }
+                scheduler.wait(now, speed).as_millis() as u32
             }),
         );
 
-        let mut receive_user_event = true;
+        let mut receive_user_event = false;
 
         'running: loop {
             // for event in self.event_pump.poll_iter() {
             let event = self.event_pump.wait_event();
             match event {
+                Event::User {
+                    code: RESYNC_EVENT, ..
+                }
+                | Event::AppDidEnterForeground { .. } => {
+                    self.update_theme(self.clock_source.now())?;
+                    self.redraw(None)?
+                }
+                Event::Window {
+                    win_event: WindowEvent::Exposed,
+                    window_id,
+                    ..
+                } => self.redraw(Some(window_id))?,
+                // The window changed size, or may have moved to a display with
+                // another resolution.
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..) | WindowEvent::Moved(..),
+                    window_id,
+                    ..
+                } => self.relayout(Some(window_id))?,
+                // A display was added, removed or changed resolution.
+                Event::Unknown { type_, .. } if type_ == SDL_EventType::SDL_DISPLAYEVENT as u32 => {
+                    self.relayout(None)?
+                }
                 Event::User { .. } => receive_user_event = true,
-                Event::Quit { .. } => break 'running,
-                Event::KeyDown { keycode, .. } => match keycode {
-                    Some(Keycode::Escape) | Some(Keycode::Q) => break 'running,
-                    _ => {}
-                },
+                // SDL only quits once the last window is closed, but closing any
+                // of the clocks ends the screen saver.
+                Event::Quit { .. }
+                | Event::Window {
+                    win_event: WindowEvent::Close,
+                    ..
+                } => break 'running,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Q),
+                    ..
+                } => break 'running,
                 _ => {}
             }
             // }
 
             if receive_user_event {
                 // println!("receive {}", receive_user_event);
-                self.render_animation();
+                let target = self.scheduler.target(self.clock_source.now());
+                let restyled = self.update_theme(target)?;
+                let shifted = self.shift.is_some_and(|shift| shift.due(self.shifted));
+                if shifted {
+                    self.shift();
+                }
+                // Either leaves the old clock on screen, so draw it anew before the flip.
+                if restyled || shifted {
+                    self.redraw(None)?;
+                }
+                self.render_animation(target)?;
                 receive_user_event = false;
             }
 
@@ -226,311 +1061,134 @@ impl<'a> ScreenSaver<'a> {
             // fill_rounded_box_b(&mut self.bg, &self.bgrect, self.radius, BACKGROUND_COLOR);
             // std::thread::sleep(std::time::Duration::from_millis(100));
         }
+
+        Ok(())
     }
 
     // fn fill_rounded_box_b(&mut self) {}
 
-    fn render_ampm(&self, surface: &mut SurfaceRef, rect: &Rect, pm: bool) {
-        let mode = format!("{}M", if pm { "P" } else { "A" });
-
-        let ampm = self.font_mode.render(&mode).blended(FONT_COLOR).unwrap();
-
-        let offset = (rect.height() as f32 * 0.127) as i32;
-        let coords = Rect::new(
-            rect.x() + (rect.height() as f32 * 0.07) as i32,
-            rect.y()
-                + if pm {
-                    rect.height() as i32 - offset - ampm.height() as i32
-                } else {
-                    offset
-                },
-            0,
-            0,
-        );
-        // surface.blit(src_rect: R1, dst: &mut SurfaceRef, dst_rect: R2)
-        ampm.blit(None, surface, coords).unwrap();
-    }
-
-    fn blit_digits(
-        &self,
-        surface: &mut SurfaceRef,
-        rect: Rect,
-        spc: i32,
-        digits: &str,
-        color: Color,
-    ) {
-        let adjust_x = if digits.starts_with('1') {
-            // println!("{}", digits);
-            (2.5 * spc as f32) as i32
-        } else {
-            0
-        };
-        // println!("adjust x={}", adjust_x);
-        let center_x = rect.x() + rect.width() as i32 / 2 - adjust_x;
-        // println!("center_x={}, rect-x={}, rect-w={}", center_x, rect.x(), rect.width());
-
-        if digits.len() > 1 {
-            let glyph_metrics = self
-                .font_time
-                .find_glyph_metrics(digits.chars().nth(1).unwrap())
-                .unwrap();
-            let glyph = self
-                .font_time
-                .render_char(digits.chars().nth(0).unwrap())
-                .blended(color)
-                .unwrap();
-
-            // dbg!(&glyph_metrics);
-            // println!("max_x={}, min_x={}", glyph_metrics.maxx, glyph_metrics.minx);
-
-            let coords = Rect::new(
-                center_x - glyph_metrics.maxx + glyph_metrics.minx
-                    - spc
-                    - if adjust_x > 0 { spc } else { 0 },
-                rect.y() + (rect.height() as i32 - glyph.height() as i32) / 2,
-                0,
-                0,
-            );
-            // dbg!(coords.x());
-            glyph.blit(None, surface, coords).unwrap();
-
-            // let _glyph_metrics = self
-            //     .font_time
-            //     .find_glyph_metrics(digits.chars().nth(1).unwrap())
-            //     .unwrap();
-            let glyph = self
-                .font_time
-                .render_char(digits.chars().nth(1).unwrap())
-                .blended(color)
-                .unwrap();
-            let coords = Rect::new(
-                center_x + spc / 2,
-                rect.y() + (rect.height() as i32 - glyph.height() as i32) / 2,
-                0,
-                0,
-            );
-            glyph.blit(None, surface, coords).unwrap();
-        } else {
-            let glyph = self.font_time.render(&digits[0..1]).blended(color).unwrap();
-            let coords = Rect::new(
-                center_x - glyph.width() as i32 / 2,
-                rect.y() + (rect.height() as i32 - glyph.height() as i32) / 2,
-                0,
-                0,
-            );
-            glyph.blit(None, surface, coords).unwrap();
-        }
-    }
-
-    fn render_digits(
-        &self,
-        surface: &mut SurfaceRef,
-        background: Rect,
-        digits: &str,
-        prevdigits: &str,
-        maxsteps: i32,
-        step: i32,
-    ) {
-        let spc = (surface.height() as f32 * 0.0125) as i32;
-        // dbg!(surface.height());
-        // dbg!(spc);
-
-        let mut rect = Rect::new(
-            background.x(),
-            background.y(),
-            background.width(),
-            background.height() / 2,
-        );
-        surface.set_clip_rect(rect);
-        self.bg.blit(None, surface, rect).unwrap();
-        self.blit_digits(surface, background, spc, digits, FONT_COLOR);
-        surface.set_clip_rect(None);
-
-        let halfsteps = maxsteps / 2;
-        let upperhalf = (step + 1) <= halfsteps;
-        let scale = if upperhalf {
-            1.0 - step as f64 / (halfsteps as f64 - 1.)
-        } else {
-            (step as f64 - halfsteps as f64 + 1.) / halfsteps as f64
-        };
-
-        let c = if upperhalf {
-            0xb7 - (0xb7 as f32 * step as f32 / (halfsteps as f32 - 1.)) as u8
-        } else {
-            (0xb7 as f32 * (step as f32 - halfsteps as f32 + 1.) / halfsteps as f32) as u8
-        };
-
-        let color = Color::RGB(c, c, c);
-
-        let mut bgcopy = self.bg.convert(&self.bg.pixel_format()).unwrap();
-
-        // let rect = Rect::new(0, 0, bgcopy.width(), bgcopy.height());
-        rect.set_x(0);
-        rect.set_y(0);
-        rect.set_width(bgcopy.width());
-        rect.set_height(bgcopy.height());
-
-        self.blit_digits(
-            &mut bgcopy,
-            rect,
-            spc,
-            if upperhalf { prevdigits } else { digits },
-            color,
-        );
-
-        let scaled = bgcopy.zoom(1., scale, true).unwrap();
-        rect.set_x(0);
-        rect.set_y(if upperhalf {
-            0
-        } else {
-            scaled.height() as i32 / 2
-        });
-        rect.set_width(scaled.width());
-        rect.set_height(scaled.height() / 2);
-        // let rect = Rect::new(
-        //     0,
-        //     if upperhalf {
-        //         0
-        //     } else {
-        //         scaled.height() as i32 / 2
-        //     },
-        //     scaled.width(),
-        //     scaled.height() / 2,
-        // );
-        let dstrect = Rect::new(
-            background.x(),
-            background.y()
-                + if upperhalf {
-                    (background.height() as i32 - scaled.height() as i32) / 2
-                } else {
-                    background.height() as i32 / 2
-                },
-            rect.width(),
-            rect.height(),
-        );
-        surface.set_clip_rect(dstrect);
-        scaled.blit(rect, surface, dstrect).unwrap();
-        surface.set_clip_rect(None);
-
-        if !self.animate {
-            return;
-        }
-
-        // Draw divider
-        // let mut rect = Rect::new(
-        //     background.x(),
-        //     background.y() + (background.height() as i32 - rect.height() as i32) / 2,
-        //     background.width(),
-        //     (surface.height() as f32 * 0.005) as u32,
-        // );
-        rect.set_height((surface.height() as f32 * 0.005) as u32);
-        rect.set_width(background.width());
-        rect.set_x(background.x());
-        rect.set_y(background.y() + (background.height() as i32 - rect.height() as i32) / 2);
-
-        surface.fill_rect(rect, Color::RGB(0, 0, 0)).unwrap();
-        rect.set_y(rect.y() + rect.height() as i32);
-        rect.set_height(1);
-        surface
-            .fill_rect(rect, Color::RGB(0x1a, 0x1a, 0x1a))
-            .unwrap();
-    }
-
-    fn render_clock(&self, maxsteps: i32, step: i32) {
-        //        let mut buffer = String::with_capacity(2);
-        //        let mut buffer2 = String::with_capacity(2);
-        // let mut buffer: Vec<u8> = Vec::with_capacity(3);
-        // let mut buffer2: Vec<u8> = Vec::with_capacity(3);
-
-        let tm = time::now();
-        let mut screen = self.window.surface(&self.event_pump).unwrap();
-
-        if tm.tm_hour != PAST_H.load(Ordering::Relaxed) as i32 {
-            let h = if self.opt.ampm {
-                (tm.tm_hour + 11) % 12 + 1
-            } else {
-                tm.tm_hour
-            };
-            // let (buffer, buffer2) = if self.opt.leadingzero {
-            //     (format!("{:02}", h), format!("{:02}", self.past_h))
-            // } else {
-            //     (format!("{}", h), format!("{}", self.past_h))
-            // };
-            let mut buffer = String::with_capacity(2);
-            let mut buffer2 = String::with_capacity(2);
-
-            if self.opt.leadingzero {
-                write!(&mut buffer, "{:02}", h).unwrap();
-                write!(&mut buffer2, "{:02}", PAST_H.load(Ordering::Relaxed)).unwrap();
-            } else {
-                write!(&mut buffer, "{}", h).unwrap();
-                write!(&mut buffer2, "{}", PAST_H.load(Ordering::Relaxed)).unwrap();
+    /// Lay the clocks of window `window_id`, or of every window for `None`, out
+    /// again for its current size and display and draw them, if either changed.
+    fn relayout(&mut self, window_id: Option<u32>) -> Result<()> {
+        let now = self.clock_source.now();
+        for screen in &mut self.screens {
+            if screen.is(window_id) && screen.relayout(self.ttf_context)? {
+                screen.redraw(&self.event_pump, now, self.debug)?;
             }
+        }
+        Ok(())
+    }
 
-            self.render_digits(
-                &mut screen,
-                self.hour_background,
-                &buffer,
-                &buffer2,
-                maxsteps,
-                step,
-            );
-            if self.opt.ampm {
-                self.render_ampm(&mut screen, &self.hour_background, tm.tm_hour >= 12);
+    /// Draw the clocks of window `window_id`, or of every window for `None`, from
+    /// scratch at the current time, after the clock jumped or the window
+    /// contents were lost.
+    fn redraw(&mut self, window_id: Option<u32>) -> Result<()> {
+        let now = self.clock_source.now();
+        for screen in &mut self.screens {
+            if screen.is(window_id) {
+                screen.redraw(&self.event_pump, now, self.debug)?;
             }
-
-            // println!("buffer: {}", buffer);
-            // println!("buffer2: {}", buffer2);
         }
+        Ok(())
+    }
 
-        if tm.tm_min != PAST_M.load(Ordering::Relaxed) as i32 {
-            let buffer = format!("{:02}", tm.tm_min);
-            let buffer2 = format!("{:02}", PAST_M.load(Ordering::Relaxed));
-            self.render_digits(
-                &mut screen,
-                self.min_background,
-                &buffer,
-                &buffer2,
-                maxsteps,
-                step,
-            );
-
-            // println!("buffer: {}", buffer);
-            // println!("buffer2: {}", buffer2);
+    /// Move every clock a step further within the margin. The clocks are still
+    /// on screen at the old place until the windows are cleared and redrawn.
+    fn shift(&mut self) {
+        // Every clock moves alike, within the room all of them have.
+        let range = self
+            .screens
+            .iter()
+            .flat_map(|screen| &screen.clocks)
+            .map(|clock| clock.renderer.offset_range())
+            .reduce(|(min, max), (other_min, other_max)| {
+                (
+                    Point::new(min.x().max(other_min.x()), min.y().max(other_min.y())),
+                    Point::new(max.x().min(other_max.x()), max.y().min(other_max.y())),
+                )
+            });
+        let offset = match range {
+            Some(range) => self.drift.step(range),
+            None => return,
+        };
+        for screen in &mut self.screens {
+            for clock in &mut screen.clocks {
+                clock.renderer.set_offset(offset);
+            }
         }
+        self.shifted = Instant::now();
+    }
 
-        //        println!("tm: {:#?}", tm);
-
-        screen.finish().unwrap();
+    /// Switch every clock to the theme night mode wants at `now`. Returns whether
+    /// the colors changed; the clocks on screen keep the old ones until redrawn.
+    fn update_theme(&mut self, now: DateTime<Utc>) -> Result<bool> {
+        let theme = match &self.night {
+            Some(night) => night.theme_at(now, &self.zone)?,
+            None => return Ok(false),
+        };
+        if theme == self.options().theme {
+            return Ok(false);
+        }
+        for screen in &mut self.screens {
+            for clock in &mut screen.clocks {
+                clock.renderer.set_theme(theme)?;
+            }
+        }
+        Ok(true)
+    }
 
-        if step == maxsteps - 1 {
-            PAST_H.store(tm.tm_hour as isize, Ordering::Relaxed);
-            PAST_M.store(tm.tm_min as isize, Ordering::Relaxed);
+    /// Draw `step` of the flip to the time at `now` on every clock of every window.
+    fn render_clock(&mut self, now: DateTime<Utc>, maxsteps: i32, step: i32) -> Result<()> {
+        for screen in &mut self.screens {
+            screen.render(&self.event_pump, now, maxsteps, step, self.debug)?;
         }
+        Ok(())
     }
 
-    fn render_animation(&self) {
-        if !self.animate {
-            self.render_clock(20, 19);
-            return;
+    /// Turn every clock over to the time at `now`, taking `self.duration` at
+    /// `self.fps` frames per second.
+    fn render_animation(&mut self, now: DateTime<Utc>) -> Result<()> {
+        if self.options().animation == Animation::Instant {
+            return self.render_clock(now, 20, 19);
         }
 
-        let duration = ::std::time::Duration::from_millis(260);
+        let duration = self.duration;
+        let frame_time = Duration::from_secs(1) / self.fps;
         //        let start_tick = self.time_subsystem.ticks();
-        let start_tick = ::std::time::Instant::now();
+        let start_tick = Instant::now();
         let end_tick = start_tick + duration;
-
-        let mut done = false;
-        while !done {
-            let mut current_tick = ::std::time::Instant::now();
+        let mut stats = FrameStats::new(self.fps);
+        let mut deadline = start_tick;
+
+        loop {
+            let frame_start = Instant::now();
+            let current_tick = frame_start.min(end_tick);
+            let frame = 99 * (current_tick - start_tick).as_micros() / duration.as_micros();
+            self.render_clock(now, 100, frame as i32)?;
+            stats.record(frame_start.elapsed());
             if current_tick >= end_tick {
-                done = true;
-                current_tick = end_tick;
+                break;
+            }
+
+            // Sleep out the rest of the frame, but always draw the last step on time.
+            // With vsync, presenting has already waited for the display.
+            deadline = (deadline + frame_time).min(end_tick);
+            if let Some(wait) = deadline.checked_duration_since(Instant::now()) {
+                std::thread::sleep(wait);
             }
-            let frame =
-                99 * (current_tick - start_tick).as_millis() / (end_tick - start_tick).as_millis();
-            self.render_clock(100, frame as i32);
         }
+
+        if self.debug {
+            let presenter = &self.screens[0].presenter;
+            let mode = match (presenter.is_accelerated(), presenter.is_vsync()) {
+                (true, true) => " (accelerated, vsync)",
+                (true, false) => " (accelerated)",
+                (false, true) => " (vsync)",
+                (false, false) => "",
+            };
+            eprintln!("gluqlo: flip{}: {}", mode, stats);
+        }
+        Ok(())
     }
 
     //     fn update_time(&mut self) -> u32 {
@@ -554,91 +1212,170 @@ impl<'a> ScreenSaver<'a> {
     //     }
 }
 
-fn main() -> Result<(), String> {
-    let opt = Opt::from_args();
+fn main() {
+    let mut opt = Opt::from_iter(xscreensaver::normalize_args(std::env::args_os()));
 
-    println!("{:#?}", opt);
+    let result = Config::load(opt.config.as_deref())
+        .and_then(|config| opt.merge(config))
+        .and_then(|_| {
+            println!("{:#?}", opt);
+            run(&opt)
+        });
+
+    if let Err(e) = result {
+        eprintln!("gluqlo: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
 
-    let sdl_context = sdl2::init()?;
-    let ttf_context = sdl2::ttf::init().unwrap();
+fn run(opt: &Opt) -> Result<()> {
+    let ttf_context = sdl2::ttf::init().map_err(|e| GluqloError::Sdl(e.to_string()))?;
 
-    let mut screen_saver = ScreenSaver::new(&sdl_context, &ttf_context, &opt);
+    if let Some(path) = &opt.render_png {
+        return render_png(&ttf_context, opt, path);
+    }
+
+    let sdl_context = sdl2::init().map_err(GluqloError::Sdl)?;
 
-    screen_saver.run();
+    // The textures of the screen saver borrow the renderers of the outputs.
+    let mut outputs = open_outputs(&sdl_context, opt)?;
+    let mut screen_saver = ScreenSaver::new(&sdl_context, &ttf_context, &mut outputs, opt)?;
 
-    Ok(())
+    screen_saver.run()
 }
 
-fn fill_rounded_box_b(dst: &mut SurfaceRef, coords: &Rect, r: i32, color: Color) {
-    let pixcolor = color.to_u32(&dst.pixel_format());
-    let rpsqrt2 = (r as f64 / 2.0_f64.sqrt()) as i32;
-    let yd: i32 = dst.pitch() as i32 / dst.pixel_format_enum().byte_size_per_pixel() as i32;
-    let mut w: i32 = coords.width() as i32 / 2 - 1;
-    let mut h: i32 = coords.height() as i32 / 2 - 1;
-    let xo = coords.x() + w as i32;
-    let yo = coords.y() + h as i32;
-
-    w -= r;
-    h -= r;
-
-    if w <= 0 || h <= 0 {
-        return;
-    }
-
-    dst.with_lock_mut(|pixels| {
-        let (_prefix, pixels, _suffix) = unsafe { pixels.align_to_mut::<u32>() };
-
-        let sy: i32 = (yo - h) * yd;
-        let ey: i32 = (yo + h) * yd;
-        let sx: i32 = xo - w;
-        let ex: i32 = xo + w;
-
-        for i in (sy..=ey).step_by(yd as usize) {
-            for j in (sx - r)..=(ex + r) {
-                // let index = (i + j) as usize;
-                // pixels[index + 0] = color.r;
-                // pixels[index + 1] = color.g;
-                // pixels[index + 2] = color.b;
-                // pixels[index + 3] = color.a;
-
-                // 如果我没理解错，就是一次赋4个值
-                //                set_pixels(pixels, i + j, pixcolor);
-                pixels[(i + j) as usize] = pixcolor;
-            }
+/// Open the windows `opt` asks for, or take over the one xscreensaver gives,
+/// each with the renderer its frames will be presented through.
+fn open_outputs(sdl_context: &Sdl, opt: &Opt) -> Result<Vec<Output>> {
+    let video_subsystem = sdl_context.video().map_err(GluqloError::Sdl)?;
+
+    let foreign = xscreensaver::target_window(opt.root, opt.window_id)?;
+    let mut windows = match foreign {
+        Some(xid) => vec![xscreensaver::window_from_xid(&video_subsystem, xid)?],
+        None => opt
+            .displays(&video_subsystem)?
+            .into_iter()
+            .map(|display| open_window(&video_subsystem, opt, display))
+            .collect::<Result<Vec<_>>>()?,
+    };
+
+    // A window that is not ours already has the size its owner chose.
+    if (opt.fullscreen || opt.all_displays) && foreign.is_none() {
+        for window in &mut windows {
+            window
+                .set_fullscreen(FullscreenType::Desktop)
+                .map_err(GluqloError::Sdl)?;
         }
+        sdl_context.mouse().show_cursor(false);
+    }
 
-        let mut d: i32 = -r;
-        let mut x2m1: i32 = -1;
-        let mut y: i32 = r;
+    let software = |window| {
+        if opt.vsync {
+            Output::vsync(window)
+        } else {
+            Ok(Output::surface(window))
+        }
+    };
+    windows
+        .into_iter()
+        .map(|window| match opt.renderer.unwrap_or_default() {
+            Backend::Software => software(window),
+            Backend::Accelerated => match Output::accelerated(window, opt.vsync) {
+                Ok(output) => Ok(output),
+                Err((window, e)) => {
+                    eprintln!("gluqlo: warning: {}, drawing in software instead", e);
+                    software(window)
+                }
+            },
+        })
+        .collect()
+}
 
-        for x in 0..=rpsqrt2 {
-            x2m1 += 2;
-            d += x2m1;
+/// Render one fully flipped frame into an offscreen surface and save it as PNG.
+///
+/// No video subsystem is initialized, so this works without a display server.
+fn render_png(ttf_context: &Sdl2TtfContext, opt: &Opt, path: &Path) -> Result<()> {
+    let now = SystemClock.now();
+    let instant = match &opt.at {
+        Some(at) => opt.zone()?.instant_on_day(now, parse_time(at, "--at")?)?,
+        None => now,
+    };
+
+    let surface = render_frame(ttf_context, opt, instant)?;
+    surface
+        .save(path)
+        .map_err(|e| GluqloError::Render(format!("cannot save {}: {}", path.display(), e)))
+}
 
-            if d >= 0 {
-                y -= 1;
-                d -= y * 2;
-            }
+/// Render the clocks at `instant` into a new `opt.width()` x `opt.height()` surface.
+fn render_frame(
+    ttf_context: &Sdl2TtfContext,
+    opt: &Opt,
+    instant: DateTime<Utc>,
+) -> Result<Surface<'static>> {
+    let mut surface = Surface::new(opt.width(), opt.height(), PixelFormatEnum::RGB888)
+        .map_err(GluqloError::Render)?;
+
+    let clocks = zone_clocks(ttf_context, opt, opt.zones()?, opt.width(), opt.height())?;
+    clocks[0].renderer.clear(&mut surface)?;
+    for clock in &clocks {
+        let time = clock.zone.time_at(instant)?;
+        clock.renderer.render(&mut surface, time, None, 20, 19)?;
+    }
 
-            for i in (sx - x)..=(ex + x) {
-                //                set_pixels(pixels, , pixcolor);
-                pixels[(sy - y * yd + i) as usize] = pixcolor;
-            }
+    Ok(surface)
+}
 
-            for i in (sx - y)..=(ex + y) {
-                //                set_pixels(pixels, sy - x * yd + i, pixcolor);
-                pixels[(sy - x * yd + i) as usize] = pixcolor;
-            }
+/// Fill `target` with the background of `renderer`.
+fn clear(renderer: &FlipClockRenderer, target: Target) -> Result<()> {
+    match target {
+        Target::Surface(surface) => renderer.clear(surface),
+        Target::Canvas(canvas) => renderer.clear_canvas(canvas),
+    }
+}
 
-            for i in (sx - y)..=(ex + y) {
-                //                set_pixels(pixels, ey + x * yd + i, pixcolor);
-                pixels[(ey + x * yd + i) as usize] = pixcolor;
-            }
+/// Parse a time of day given as `HH:MM` or `HH:MM:SS` for `flag`.
+fn parse_time(value: &str, flag: &str) -> Result<Time> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M"))
+        .map(Time::from)
+        .map_err(|e| {
+            GluqloError::Config(format!(
+                "invalid {} {:?}, expected HH:MM[:SS]: {}",
+                flag, value, e
+            ))
+        })
+}
 
-            for i in (sx - x)..=(ex + x) {
-                //                set_pixels(pixels, ey + y * yd + i, pixcolor);
-                pixels[(ey + y * yd + i) as usize] = pixcolor;
-            }
-        }
-    });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font_arg(s: &str) -> (PathBuf, u32) {
+        let arg: FontArg = s.parse().unwrap();
+        (arg.path, arg.index)
+    }
+
+    #[test]
+    fn font_args_take_a_face_index() {
+        assert_eq!(font_arg("a.ttc:2"), (PathBuf::from("a.ttc"), 2));
+        assert_eq!(font_arg("/fonts/a.ttc"), (PathBuf::from("/fonts/a.ttc"), 0));
+        // Only a number after the last colon is an index.
+        assert_eq!(font_arg(r"C:\f.ttc"), (PathBuf::from(r"C:\f.ttc"), 0));
+        assert_eq!(font_arg(r"C:\f.ttc:1"), (PathBuf::from(r"C:\f.ttc"), 1));
+        assert_eq!(font_arg("a.ttf:"), (PathBuf::from("a.ttf:"), 0));
+        assert!("a.ttc:99999999999".parse::<FontArg>().is_err());
+    }
+
+    #[test]
+    fn date_formats_must_write_something() {
+        let date_format = |args: &[&str]| Opt::from_iter(args).date_format();
+        assert_eq!(date_format(&["gluqlo"]).unwrap(), None);
+        assert_eq!(
+            date_format(&["gluqlo", "--date", "%d"]).unwrap().as_deref(),
+            Some("%d")
+        );
+        assert!(date_format(&["gluqlo", "--date", ""]).is_err());
+        assert!(date_format(&["gluqlo", "--date", "%H"]).is_err());
+    }
 }
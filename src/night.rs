@@ -0,0 +1,207 @@
+use crate::{GluqloError, Result, Theme, Time, Zone};
+use chrono::{DateTime, NaiveTime, Utc};
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+/// Minutes a fixed-hours schedule takes to fade between day and night.
+const FADE_MINUTES: f64 = 30.;
+/// Sun elevation, in degrees, at which the fade to night starts: sunset, with
+/// the refraction of the atmosphere and the size of the sun's disc.
+const SUNSET_ELEVATION: f64 = -0.833;
+/// Sun elevation at which it is fully night: the end of civil twilight.
+const NIGHT_ELEVATION: f64 = -6.;
+/// Steps the fade between day and night is rounded to. Each new level redraws
+/// the whole clock, so it shouldn't change with every tick of `--seconds`.
+const LEVELS: f64 = 64.;
+
+/// When it is night.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NightSchedule {
+    /// From `dusk` to `dawn` on the wall clock, fading over half an hour after each.
+    Hours { dusk: Time, dawn: Time },
+    /// From sunset to sunrise at this place, fading through civil twilight.
+    /// Worked out from the position of the sun, so no network is needed.
+    Sun { latitude: f64, longitude: f64 },
+}
+
+impl NightSchedule {
+    /// How far into the night it is at `instant`, from 0 (day) to 1 (night).
+    /// `zone` gives the wall clock [`NightSchedule::Hours`] are read on.
+    pub fn level(&self, instant: DateTime<Utc>, zone: &Zone) -> Result<f32> {
+        let level = match *self {
+            NightSchedule::Hours { dusk, dawn } => {
+                let now = minutes(zone.time_at(instant)?);
+                let since = |start: Time| (now - minutes(start)).rem_euclid(24. * 60.);
+                if since(dusk) < since(dawn) {
+                    since(dusk) / FADE_MINUTES
+                } else {
+                    1. - since(dawn) / FADE_MINUTES
+                }
+            }
+            NightSchedule::Sun {
+                latitude,
+                longitude,
+            } => {
+                let elevation = sun_elevation(instant, latitude, longitude);
+                (SUNSET_ELEVATION - elevation) / (SUNSET_ELEVATION - NIGHT_ELEVATION)
+            }
+        };
+        let t = level.clamp(0., 1.);
+        // Ease in and out so the change is hard to notice at either end.
+        let eased = t * t * (3. - 2. * t);
+        Ok(((eased * LEVELS).round() / LEVELS) as f32)
+    }
+}
+
+impl FromStr for NightSchedule {
+    type Err = GluqloError;
+
+    /// Parse fixed hours written as `HH:MM-HH:MM`, from dusk to dawn.
+    fn from_str(s: &str) -> Result<NightSchedule> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            GluqloError::Config(format!(
+                "invalid night hours {:?}, expected HH:MM-HH:MM: {}",
+                s, e
+            ))
+        };
+        let (dusk, dawn) = s.split_once('-').ok_or_else(|| invalid(&"missing `-`"))?;
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map(Time::from)
+                .map_err(|e| invalid(&e))
+        };
+        Ok(NightSchedule::Hours {
+            dusk: parse(dusk)?,
+            dawn: parse(dawn)?,
+        })
+    }
+}
+
+/// Fades the theme between a day and a night palette on a [`NightSchedule`].
+#[derive(Debug, Clone)]
+pub struct NightMode {
+    pub schedule: NightSchedule,
+    pub day: Theme,
+    pub night: Theme,
+    /// Brightness of the night palette, from 0 (black) to 1 (as given).
+    pub brightness: f32,
+}
+
+impl NightMode {
+    /// The theme to show at `instant`, reading fixed hours on the wall clock of `zone`.
+    pub fn theme_at(&self, instant: DateTime<Utc>, zone: &Zone) -> Result<Theme> {
+        let level = self.schedule.level(instant, zone)?;
+        Ok(self.day.mix(&self.night.dimmed(self.brightness), level))
+    }
+}
+
+/// Minutes since midnight.
+fn minutes(time: Time) -> f64 {
+    (time.hour * 60 + time.minute) as f64 + time.second as f64 / 60.
+}
+
+/// Height of the sun above the horizon at `instant`, in degrees, seen from
+/// `latitude` and `longitude` (east positive).
+///
+/// This is the low-precision solar position from the Astronomical Almanac,
+/// good to about a hundredth of a degree, which is plenty for fading a theme.
+fn sun_elevation(instant: DateTime<Utc>, latitude: f64, longitude: f64) -> f64 {
+    let rad = PI / 180.;
+    // Days since the J2000 epoch, 2000-01-01 12:00 UTC.
+    let n = (instant.timestamp_millis() as f64 / 86_400_000.) - 10_957.5;
+
+    let mean_longitude = 280.460 + 0.985_647_4 * n;
+    let anomaly = (357.528 + 0.985_600_3 * n) * rad;
+    let ecliptic_longitude =
+        (mean_longitude + 1.915 * anomaly.sin() + 0.020 * (2. * anomaly).sin()) * rad;
+    let obliquity = (23.439 - 0.000_000_4 * n) * rad;
+
+    let right_ascension =
+        (obliquity.cos() * ecliptic_longitude.sin()).atan2(ecliptic_longitude.cos());
+    let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+
+    let sidereal_time = (280.460_618_37 + 360.985_647_366_29 * n + longitude) * rad;
+    let hour_angle = sidereal_time - right_ascension;
+
+    let latitude = latitude * rad;
+    (latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos())
+        .asin()
+        / rad
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const BERLIN: NightSchedule = NightSchedule::Sun {
+        latitude: 52.52,
+        longitude: 13.405,
+    };
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2021, 6, 21, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn finds_the_sun() {
+        // Solar noon at the summer solstice: 90° - latitude + the tilt of the axis.
+        let noon = sun_elevation(at(11, 7), 52.52, 13.405);
+        assert!((noon - 60.9).abs() < 0.3, "{}", noon);
+        let midnight = sun_elevation(at(23, 7), 52.52, 13.405);
+        assert!((midnight + 14.0).abs() < 0.3, "{}", midnight);
+    }
+
+    #[test]
+    fn sun_schedule_fades_through_twilight() {
+        let utc = Zone::named("UTC").unwrap();
+        assert_eq!(BERLIN.level(at(12, 0), &utc).unwrap(), 0.);
+        assert_eq!(BERLIN.level(at(23, 0), &utc).unwrap(), 1.);
+        // Sunset in Berlin is at 19:33 UTC, civil twilight ends at 20:21.
+        let dusk = BERLIN.level(at(19, 55), &utc).unwrap();
+        assert!(dusk > 0.2 && dusk < 0.8, "{}", dusk);
+    }
+
+    #[test]
+    fn hours_fade_after_dusk_and_dawn() {
+        let utc = Zone::named("UTC").unwrap();
+        let hours: NightSchedule = "22:00-06:30".parse().unwrap();
+        let level = |hour, minute| hours.level(at(hour, minute), &utc).unwrap();
+        assert_eq!(level(21, 0), 0.);
+        assert_eq!(level(22, 0), 0.);
+        assert_eq!(level(22, 15), 0.5);
+        assert_eq!(level(23, 0), 1.);
+        assert_eq!(level(6, 30), 1.);
+        assert_eq!(level(6, 45), 0.5);
+        assert_eq!(level(12, 0), 0.);
+
+        // Levels change in steps, not with every second of the fade.
+        let levels: Vec<_> = (0..60)
+            .map(|second| {
+                let instant = at(22, 15) + chrono::Duration::seconds(second);
+                hours.level(instant, &utc).unwrap()
+            })
+            .collect();
+        let changes = levels.windows(2).filter(|pair| pair[0] != pair[1]).count();
+        assert!(changes <= 3, "{:?}", levels);
+
+        assert!("22:00".parse::<NightSchedule>().is_err());
+        assert!("25:00-06:00".parse::<NightSchedule>().is_err());
+    }
+
+    #[test]
+    fn night_mode_mixes_the_palettes() {
+        let utc = Zone::named("UTC").unwrap();
+        let mode = NightMode {
+            schedule: BERLIN,
+            day: Theme::CLASSIC,
+            night: Theme::NIGHT,
+            brightness: 0.5,
+        };
+        assert_eq!(mode.theme_at(at(12, 0), &utc).unwrap(), Theme::CLASSIC);
+        assert_eq!(
+            mode.theme_at(at(23, 0), &utc).unwrap(),
+            Theme::NIGHT.dimmed(0.5)
+        );
+    }
+}
@@ -0,0 +1,860 @@
+use crate::{FlipClockRenderer, GluqloError, Result};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Canvas, RenderTarget, Texture, TextureCreator};
+use sdl2::surface::{Surface, SurfaceRef};
+use sdl2::ttf::Font;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Add;
+
+/// What a card shows.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Face<'a> {
+    pub digits: &'a str,
+    pub color: Color,
+}
+
+/// The drawing operations of the clock, so it can be drawn by the CPU into a
+/// surface or by the GPU through an SDL renderer.
+pub(crate) trait Painter {
+    /// Fill `rect`, or all of the target, with `color`, replacing what was there.
+    fn fill(&mut self, rect: Option<Rect>, color: Color) -> Result<()>;
+
+    /// Draw the upper or lower half of the card at `card` showing `face`.
+    fn card_half(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        upper: bool,
+    ) -> Result<()> {
+        self.card(clock, card, face, half(card, upper))
+    }
+
+    /// Draw the card showing `face` at `at`, clipped to `clip`.
+    fn card(&mut self, clock: &FlipClockRenderer, at: Rect, face: Face, clip: Rect) -> Result<()>;
+
+    /// Draw only the digits of `face` as they sit on the card at `card`,
+    /// clipped to `clip`.
+    fn digits(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        clip: Rect,
+    ) -> Result<()>;
+
+    /// Draw the upper or lower half of the flap showing `face`, squashed to
+    /// `scale` of its height toward the middle of the card at `card`.
+    fn flap(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        upper: bool,
+        scale: f64,
+    ) -> Result<()>;
+
+    /// Draw the upper or lower half of the card at `card` showing `face` as a
+    /// flap folded `tilt` radians out of the card toward the viewer around the
+    /// hinge in its middle, lit to `light` of its full brightness.
+    fn fold(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        upper: bool,
+        tilt: f64,
+        light: f32,
+    ) -> Result<()>;
+
+    /// Darken `rect` by blending black at `alpha` over it.
+    fn shade(&mut self, rect: Rect, alpha: u8) -> Result<()>;
+
+    /// Draw `text` with its top left corner where `place` puts it given the
+    /// size of the rendered text, clipped to `clip`.
+    fn text(
+        &mut self,
+        font: &Font,
+        text: &str,
+        color: Color,
+        place: &dyn Fn(u32, u32) -> Point,
+        clip: Option<Rect>,
+    ) -> Result<()>;
+}
+
+/// The rows of the card at `card` covered by its upper or lower half.
+pub(crate) fn half(card: Rect, upper: bool) -> Rect {
+    let y = if upper {
+        card.y()
+    } else {
+        card.y() + card.height() as i32 / 2
+    };
+    Rect::new(card.x(), y, card.width(), card.height() / 2)
+}
+
+/// The card at `card` squashed to `scale` of its height toward its middle:
+/// where each part of the card ends up, and the part of the upper or lower
+/// flap left to see. `None` when the flap is too thin to see.
+fn squash(card: Rect, upper: bool, scale: f64) -> Option<(impl Fn(Rect) -> Rect, Rect)> {
+    let height = (card.height() as f64 * scale).round() as u32;
+    if height / 2 == 0 {
+        return None;
+    }
+
+    let top = card.y() + (card.height() as i32 - height as i32) / 2;
+    let map = move |rect: Rect| {
+        Rect::new(
+            rect.x(),
+            top + ((rect.y() - card.y()) as f64 * scale).round() as i32,
+            rect.width(),
+            ((rect.height() as f64 * scale).round() as u32).max(1),
+        )
+    };
+    let visible = Rect::new(
+        card.x(),
+        if upper {
+            top
+        } else {
+            card.y() + card.height() as i32 / 2
+        },
+        card.width(),
+        height / 2,
+    );
+    Some((map, visible))
+}
+
+/// Distance of the viewer from a folding flap, in card heights. Closer makes
+/// the perspective stronger.
+const FOLD_DISTANCE: f64 = 2.5;
+
+/// One screen row of a folded flap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FoldRow {
+    /// Row on the screen.
+    dst: i32,
+    /// Row of the card drawn there.
+    src: i32,
+    /// How many times as wide as the card the row appears, being nearer.
+    widen: f64,
+}
+
+/// The screen rows of the upper or lower half of `card` folded `tilt` radians
+/// toward the viewer around the hinge in the middle of the card.
+///
+/// Each screen row is traced back to the card row seen there, so the flap has
+/// no gaps however much it is stretched.
+fn fold_rows(card: Rect, upper: bool, tilt: f64) -> Vec<FoldRow> {
+    let mid = card.height() as i32 / 2;
+    let half = mid as f64;
+    let distance = FOLD_DISTANCE * card.height() as f64;
+    let (sin, cos) = tilt.sin_cos();
+    (0..mid)
+        .map_while(|t| {
+            // A card row `r` from the hinge appears `r cos * d / (d - r sin)` from it.
+            let seen = t as f64 + 0.5;
+            let denominator = cos * distance + seen * sin;
+            let r = seen * distance / denominator;
+            if denominator <= 0. || r >= half {
+                return None;
+            }
+            let r_row = r as i32;
+            let (dst, src) = if upper {
+                (mid - 1 - t, mid - 1 - r_row)
+            } else {
+                (mid + t, mid + r_row)
+            };
+            Some(FoldRow {
+                dst: card.y() + dst,
+                src,
+                widen: distance / (distance - r * sin),
+            })
+        })
+        .collect()
+}
+
+/// Left edge and width on screen of the part of a card that starts `x` from
+/// its left edge and is `width` wide, when the card is `card_width` wide,
+/// centered on `center_x` and stretched `widen` times across.
+fn widened(center_x: i32, card_width: u32, x: i32, width: u32, widen: f64) -> (i32, u32) {
+    let left = center_x as f64 + (x as f64 - card_width as f64 / 2.) * widen;
+    (left.round() as i32, (width as f64 * widen).round() as u32)
+}
+
+/// Most entries kept per kind before a cache starts over, since every card
+/// size the clock is laid out at keeps entries of its own.
+const MAX_ENTRIES: usize = 512;
+
+/// How often the caches of a [`FlipClockRenderer`] had what was asked for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Glyphs, cards and textures held now.
+    pub entries: usize,
+}
+
+impl CacheStats {
+    /// Fraction of lookups that were hits, 0 before the first one.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+impl Add for CacheStats {
+    type Output = CacheStats;
+
+    fn add(self, other: CacheStats) -> CacheStats {
+        CacheStats {
+            hits: self.hits + other.hits,
+            misses: self.misses + other.misses,
+            entries: self.entries + other.entries,
+        }
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cache: {} entries, {} hits, {} misses ({:.1}% hits)",
+            self.entries,
+            self.hits,
+            self.misses,
+            self.hit_rate() * 100.
+        )
+    }
+}
+
+/// A digit rendered on its own or as one of a pair, which SDL_ttf lays out
+/// slightly differently, at one card size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    c: char,
+    alone: bool,
+    size: u32,
+}
+
+/// A card with its digits drawn on in the theme's digit color, at one card size.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CardKey {
+    digits: String,
+    color: Color,
+    size: u32,
+}
+
+/// Glyphs and whole card faces already drawn in software, so a flip doesn't
+/// render text and compose cards again on every frame.
+///
+/// Glyphs are white and tinted with a color mod when blitted, like
+/// [`TextureCache`]. Cards are only composed for faces shown whole in the
+/// theme's digit color; fading and squashed faces are drawn by tinting the
+/// cached glyphs over the blank card, so the fade levels of a flip don't each
+/// keep a card. Entries are keyed by the card size, and
+/// [`FlipClockRenderer::set_theme`] empties the cache.
+#[derive(Default)]
+pub(crate) struct Surfaces {
+    glyphs: HashMap<GlyphKey, Surface<'static>>,
+    cards: HashMap<CardKey, Surface<'static>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl Surfaces {
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.glyphs.len() + self.cards.len(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.cards.clear();
+    }
+
+    fn count(&mut self, hit: bool) {
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+    }
+
+    /// Make sure the glyph for `key` is cached.
+    fn render_glyph(&mut self, font: &Font, key: GlyphKey) -> Result<()> {
+        let hit = self.glyphs.contains_key(&key);
+        self.count(hit);
+        if hit {
+            return Ok(());
+        }
+
+        let glyph = if key.alone {
+            font.render(key.c.encode_utf8(&mut [0; 4]))
+                .blended(Color::WHITE)
+        } else {
+            font.render_char(key.c).blended(Color::WHITE)
+        }
+        .map_err(|e| GluqloError::Render(e.to_string()))?;
+        if self.glyphs.len() >= MAX_ENTRIES {
+            self.glyphs.clear();
+        }
+        self.glyphs.insert(key, glyph);
+        Ok(())
+    }
+
+    /// Blit `digits` in `color` onto the card at `rect` of `surface`, each
+    /// glyph rectangle passed through `map`.
+    fn blit_digits(
+        &mut self,
+        clock: &FlipClockRenderer,
+        surface: &mut SurfaceRef,
+        rect: Rect,
+        face: Face,
+        map: impl Fn(Rect) -> Rect,
+    ) -> Result<()> {
+        let Face { digits, color } = face;
+        let alone = digits.len() == 1;
+        let keys: Vec<_> = digits
+            .chars()
+            .take(2)
+            .map(|c| GlyphKey {
+                c,
+                alone,
+                size: clock.bg.height(),
+            })
+            .collect();
+        for &key in &keys {
+            self.render_glyph(&clock.font_time, key)?;
+        }
+
+        let sizes: Vec<_> = keys.iter().map(|key| self.glyphs[key].size()).collect();
+        let points = clock.place_digits(rect, digits, &sizes)?;
+        for (key, point) in keys.iter().zip(points) {
+            let glyph = self.glyphs.get_mut(key).expect("glyph rendered");
+            glyph.set_color_mod(Color::RGB(color.r, color.g, color.b));
+            glyph.set_alpha_mod(color.a);
+            let dst = map(Rect::new(
+                point.x(),
+                point.y(),
+                glyph.width(),
+                glyph.height(),
+            ));
+            if dst.size() == glyph.size() {
+                glyph.blit(None, surface, dst)
+            } else {
+                glyph.blit_scaled(None, surface, dst)
+            }
+            .map_err(GluqloError::Render)?;
+        }
+        Ok(())
+    }
+
+    /// The card showing `face`, composed on first use.
+    fn card(&mut self, clock: &FlipClockRenderer, face: Face) -> Result<&mut Surface<'static>> {
+        let key = CardKey {
+            digits: face.digits.to_owned(),
+            color: face.color,
+            size: clock.bg.height(),
+        };
+        let hit = self.cards.contains_key(&key);
+        self.count(hit);
+        if !hit {
+            let mut card = clock
+                .bg
+                .convert(&clock.bg.pixel_format())
+                .map_err(GluqloError::Render)?;
+            let rect = Rect::new(0, 0, card.width(), card.height());
+            self.blit_digits(clock, &mut card, rect, face, |rect| rect)?;
+            if self.cards.len() >= MAX_ENTRIES {
+                self.cards.clear();
+            }
+            self.cards.insert(key.clone(), card);
+        }
+        Ok(self.cards.get_mut(&key).expect("card composed"))
+    }
+
+    /// Blit the blank card and the tinted glyphs of `face` onto `surface`, each
+    /// rectangle of the card at `card` passed through `map`.
+    fn blit_card(
+        &mut self,
+        clock: &FlipClockRenderer,
+        surface: &mut SurfaceRef,
+        card: Rect,
+        face: Face,
+        map: impl Fn(Rect) -> Rect,
+    ) -> Result<()> {
+        let dst = map(card);
+        if dst.size() == clock.bg.size() {
+            clock.bg.blit(None, surface, dst)
+        } else {
+            clock.bg.blit_scaled(None, surface, dst)
+        }
+        .map_err(GluqloError::Render)?;
+        self.blit_digits(clock, surface, card, face, map)
+    }
+}
+
+/// Draws into a surface with SDL blits and SDL_gfx zooming.
+pub(crate) struct SurfacePainter<'a> {
+    pub surface: &'a mut SurfaceRef,
+    pub cache: &'a mut Surfaces,
+}
+
+impl Painter for SurfacePainter<'_> {
+    fn fill(&mut self, rect: Option<Rect>, color: Color) -> Result<()> {
+        self.surface
+            .fill_rect(rect, color)
+            .map_err(GluqloError::Render)
+    }
+
+    fn card(&mut self, clock: &FlipClockRenderer, at: Rect, face: Face, clip: Rect) -> Result<()> {
+        self.surface.set_clip_rect(clip);
+        // A fading face would need a card per fade level; tint the glyphs
+        // over the blank card instead.
+        let drawn = if face.color == clock.options().theme.digits {
+            match self.cache.card(clock, face) {
+                Ok(composed) => composed
+                    .blit(None, self.surface, at)
+                    .map(|_| ())
+                    .map_err(GluqloError::Render),
+                Err(e) => Err(e),
+            }
+        } else {
+            self.cache
+                .blit_card(clock, self.surface, at, face, |rect| rect)
+        };
+        self.surface.set_clip_rect(None);
+        drawn
+    }
+
+    fn digits(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        clip: Rect,
+    ) -> Result<()> {
+        self.surface.set_clip_rect(clip);
+        let drawn = self
+            .cache
+            .blit_digits(clock, self.surface, card, face, |rect| rect);
+        self.surface.set_clip_rect(None);
+        drawn
+    }
+
+    fn flap(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        upper: bool,
+        scale: f64,
+    ) -> Result<()> {
+        let (squash, visible) = match squash(card, upper, scale) {
+            Some(squashed) => squashed,
+            None => return Ok(()),
+        };
+        self.surface.set_clip_rect(visible);
+        let drawn = self
+            .cache
+            .blit_card(clock, self.surface, card, face, squash);
+        self.surface.set_clip_rect(None);
+        drawn
+    }
+
+    fn fold(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        upper: bool,
+        tilt: f64,
+        light: f32,
+    ) -> Result<()> {
+        let rows = fold_rows(card, upper, tilt);
+        let center_x = card.x() + card.width() as i32 / 2;
+        let composed = self.cache.card(clock, face)?;
+        let level = (light.clamp(0., 1.) * 255.).round() as u8;
+        composed.set_color_mod(Color::RGB(level, level, level));
+
+        // Rows nearer the viewer are wider than the card; keep them on it.
+        let surface = &mut *self.surface;
+        surface.set_clip_rect(card);
+        let drawn = rows.iter().try_for_each(|row| {
+            let (x, width) = widened(center_x, card.width(), 0, card.width(), row.widen);
+            composed
+                .blit_scaled(
+                    Rect::new(0, row.src, card.width(), 1),
+                    &mut *surface,
+                    Rect::new(x, row.dst, width, 1),
+                )
+                .map(|_| ())
+                .map_err(GluqloError::Render)
+        });
+        surface.set_clip_rect(None);
+        composed.set_color_mod(Color::WHITE);
+        drawn
+    }
+
+    fn shade(&mut self, rect: Rect, alpha: u8) -> Result<()> {
+        let mut shadow =
+            Surface::new(1, 1, PixelFormatEnum::RGBA32).map_err(GluqloError::Render)?;
+        shadow
+            .fill_rect(None, Color::RGBA(0, 0, 0, alpha))
+            .map_err(GluqloError::Render)?;
+        shadow
+            .set_blend_mode(BlendMode::Blend)
+            .map_err(GluqloError::Render)?;
+        shadow
+            .blit_scaled(None, self.surface, rect)
+            .map(|_| ())
+            .map_err(GluqloError::Render)
+    }
+
+    fn text(
+        &mut self,
+        font: &Font,
+        text: &str,
+        color: Color,
+        place: &dyn Fn(u32, u32) -> Point,
+        clip: Option<Rect>,
+    ) -> Result<()> {
+        let rendered = font
+            .render(text)
+            .blended(color)
+            .map_err(|e| GluqloError::Render(e.to_string()))?;
+        let point = place(rendered.width(), rendered.height());
+        self.surface.set_clip_rect(clip);
+        let drawn = rendered
+            .blit(None, self.surface, Rect::new(point.x(), point.y(), 0, 0))
+            .map(|_| ())
+            .map_err(GluqloError::Render);
+        self.surface.set_clip_rect(None);
+        drawn
+    }
+}
+
+/// Card and glyph textures [`FlipClockRenderer::render_canvas`] keeps on the
+/// GPU between frames.
+///
+/// The textures are made by `creator`, so only its SDL renderer can draw them,
+/// and they are freed with the cache, which cannot outlive the creator. Entries
+/// are keyed by the card size and color, so one cache serves clocks with the
+/// same fonts through relayouts and theme changes.
+pub struct TextureCache<'r, C> {
+    creator: &'r TextureCreator<C>,
+    /// The blank card, with the size and color it was drawn at.
+    card: Option<((u32, Color), Texture<'r>)>,
+    /// White glyphs, tinted with a color mod when drawn.
+    glyphs: HashMap<GlyphKey, Texture<'r>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl<'r, C> TextureCache<'r, C> {
+    /// An empty cache for textures made by `creator`.
+    pub fn new(creator: &'r TextureCreator<C>) -> TextureCache<'r, C> {
+        TextureCache {
+            creator,
+            card: None,
+            glyphs: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.card.iter().count() + self.glyphs.len(),
+        }
+    }
+
+    fn card(&mut self) -> &mut Texture<'r> {
+        &mut self.card.as_mut().expect("card texture prepared").1
+    }
+}
+
+/// Draws through an SDL renderer, from cached textures of the card and glyphs.
+pub(crate) struct CanvasPainter<'a, 'r, T: RenderTarget> {
+    pub canvas: &'a mut Canvas<T>,
+    pub textures: &'a mut TextureCache<'r, T::Context>,
+}
+
+fn texture_error(e: impl ToString) -> GluqloError {
+    GluqloError::Render(e.to_string())
+}
+
+impl<T: RenderTarget> CanvasPainter<'_, '_, T> {
+    /// The card texture and the glyphs of `digits` with where they go on `card`,
+    /// uploading whatever is not cached yet.
+    fn prepare(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        digits: &str,
+    ) -> Result<Vec<(GlyphKey, Rect)>> {
+        let size = clock.bg.height();
+        let card_key = (size, clock.options().theme.card);
+        if self.textures.card.as_ref().map(|(key, _)| *key) != Some(card_key) {
+            let mut texture = self
+                .textures
+                .creator
+                .create_texture_from_surface(&clock.bg)
+                .map_err(texture_error)?;
+            texture.set_blend_mode(BlendMode::Blend);
+            self.textures.card = Some((card_key, texture));
+        }
+
+        let alone = digits.len() == 1;
+        let keys: Vec<_> = digits
+            .chars()
+            .take(2)
+            .map(|c| GlyphKey { c, alone, size })
+            .collect();
+        for &key in &keys {
+            if self.textures.glyphs.contains_key(&key) {
+                self.textures.hits += 1;
+            } else {
+                self.textures.misses += 1;
+                let mut buf = [0; 4];
+                let surface = if alone {
+                    clock.font_time.render(key.c.encode_utf8(&mut buf))
+                } else {
+                    clock.font_time.render_char(key.c)
+                }
+                .blended(Color::WHITE)
+                .map_err(texture_error)?;
+                let mut texture = self
+                    .textures
+                    .creator
+                    .create_texture_from_surface(&surface)
+                    .map_err(texture_error)?;
+                texture.set_blend_mode(BlendMode::Blend);
+                if self.textures.glyphs.len() >= MAX_ENTRIES {
+                    self.textures.glyphs.clear();
+                }
+                self.textures.glyphs.insert(key, texture);
+            }
+        }
+
+        let sizes: Vec<_> = keys
+            .iter()
+            .map(|key| {
+                let query = self.textures.glyphs[key].query();
+                (query.width, query.height)
+            })
+            .collect();
+        let points = clock.place_digits(card, digits, &sizes)?;
+        Ok(keys
+            .into_iter()
+            .zip(sizes)
+            .zip(points)
+            .map(|((key, (w, h)), p)| (key, Rect::new(p.x(), p.y(), w, h)))
+            .collect())
+    }
+
+    /// Draw the card with `glyphs` in `color`, each rectangle passed through `map`.
+    fn draw_card(
+        &mut self,
+        card: Rect,
+        glyphs: &[(GlyphKey, Rect)],
+        color: Color,
+        map: impl Fn(Rect) -> Rect,
+    ) -> Result<()> {
+        let texture = self.textures.card();
+        self.canvas
+            .copy(texture, None, map(card))
+            .map_err(GluqloError::Render)?;
+        self.draw_glyphs(glyphs, color, map)
+    }
+
+    /// Draw `glyphs` in `color`, each rectangle passed through `map`.
+    fn draw_glyphs(
+        &mut self,
+        glyphs: &[(GlyphKey, Rect)],
+        color: Color,
+        map: impl Fn(Rect) -> Rect,
+    ) -> Result<()> {
+        for (key, rect) in glyphs {
+            let texture = self.textures.glyphs.get_mut(key).expect("glyph prepared");
+            texture.set_color_mod(color.r, color.g, color.b);
+            texture.set_alpha_mod(color.a);
+            self.canvas
+                .copy(texture, None, map(*rect))
+                .map_err(GluqloError::Render)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: RenderTarget> CanvasPainter<'_, '_, T> {
+    /// Draw one row of a folded card: card row `row.src` with `glyphs` in
+    /// `color`, widened around `center_x`.
+    fn draw_fold_row(
+        &mut self,
+        card: Rect,
+        glyphs: &[(GlyphKey, Rect)],
+        color: Color,
+        row: FoldRow,
+    ) -> Result<()> {
+        let center_x = card.x() + card.width() as i32 / 2;
+        let (x, width) = widened(center_x, card.width(), 0, card.width(), row.widen);
+        let texture = self.textures.card();
+        self.canvas
+            .copy(
+                texture,
+                Rect::new(0, row.src, card.width(), 1),
+                Rect::new(x, row.dst, width, 1),
+            )
+            .map_err(GluqloError::Render)?;
+
+        let y = card.y() + row.src;
+        for (key, rect) in glyphs {
+            if y < rect.y() || y >= rect.bottom() {
+                continue;
+            }
+            let (x, width) = widened(
+                center_x,
+                card.width(),
+                rect.x() - card.x(),
+                rect.width(),
+                row.widen,
+            );
+            let texture = self.textures.glyphs.get_mut(key).expect("glyph prepared");
+            texture.set_color_mod(color.r, color.g, color.b);
+            texture.set_alpha_mod(color.a);
+            self.canvas
+                .copy(
+                    texture,
+                    Rect::new(0, y - rect.y(), rect.width(), 1),
+                    Rect::new(x, row.dst, width, 1),
+                )
+                .map_err(GluqloError::Render)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: RenderTarget> Painter for CanvasPainter<'_, '_, T> {
+    fn fill(&mut self, rect: Option<Rect>, color: Color) -> Result<()> {
+        self.canvas.set_blend_mode(BlendMode::None);
+        self.canvas.set_draw_color(color);
+        self.canvas.fill_rect(rect).map_err(GluqloError::Render)
+    }
+
+    fn card(&mut self, clock: &FlipClockRenderer, at: Rect, face: Face, clip: Rect) -> Result<()> {
+        let glyphs = self.prepare(clock, at, face.digits)?;
+        self.canvas.set_clip_rect(clip);
+        let drawn = self.draw_card(at, &glyphs, face.color, |rect| rect);
+        self.canvas.set_clip_rect(None);
+        drawn
+    }
+
+    fn digits(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        clip: Rect,
+    ) -> Result<()> {
+        let glyphs = self.prepare(clock, card, face.digits)?;
+        self.canvas.set_clip_rect(clip);
+        let drawn = self.draw_glyphs(&glyphs, face.color, |rect| rect);
+        self.canvas.set_clip_rect(None);
+        drawn
+    }
+
+    fn flap(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        upper: bool,
+        scale: f64,
+    ) -> Result<()> {
+        let (squash, visible) = match squash(card, upper, scale) {
+            Some(squashed) => squashed,
+            None => return Ok(()),
+        };
+        // The whole card squashed toward its middle, as a textured quad per part.
+        let glyphs = self.prepare(clock, card, face.digits)?;
+        self.canvas.set_clip_rect(visible);
+        let drawn = self.draw_card(card, &glyphs, face.color, squash);
+        self.canvas.set_clip_rect(None);
+        drawn
+    }
+
+    fn fold(
+        &mut self,
+        clock: &FlipClockRenderer,
+        card: Rect,
+        face: Face,
+        upper: bool,
+        tilt: f64,
+        light: f32,
+    ) -> Result<()> {
+        let glyphs = self.prepare(clock, card, face.digits)?;
+        let level = (light.clamp(0., 1.) * 255.).round() as u8;
+        let dim = |channel: u8| (channel as u16 * level as u16 / 255) as u8;
+        let color = Color::RGBA(
+            dim(face.color.r),
+            dim(face.color.g),
+            dim(face.color.b),
+            face.color.a,
+        );
+
+        // Rows nearer the viewer are wider than the card; keep them on it.
+        self.canvas.set_clip_rect(card);
+        self.textures.card().set_color_mod(level, level, level);
+        let drawn = fold_rows(card, upper, tilt)
+            .into_iter()
+            .try_for_each(|row| self.draw_fold_row(card, &glyphs, color, row));
+        self.textures.card().set_color_mod(255, 255, 255);
+        self.canvas.set_clip_rect(None);
+        drawn
+    }
+
+    fn shade(&mut self, rect: Rect, alpha: u8) -> Result<()> {
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(Color::RGBA(0, 0, 0, alpha));
+        self.canvas.fill_rect(rect).map_err(GluqloError::Render)
+    }
+
+    fn text(
+        &mut self,
+        font: &Font,
+        text: &str,
+        color: Color,
+        place: &dyn Fn(u32, u32) -> Point,
+        clip: Option<Rect>,
+    ) -> Result<()> {
+        let rendered = font.render(text).blended(color).map_err(texture_error)?;
+        let texture = self
+            .textures
+            .creator
+            .create_texture_from_surface(&rendered)
+            .map_err(texture_error)?;
+        let point = place(rendered.width(), rendered.height());
+        self.canvas.set_clip_rect(clip);
+        let drawn = self
+            .canvas
+            .copy(
+                &texture,
+                None,
+                Rect::new(point.x(), point.y(), rendered.width(), rendered.height()),
+            )
+            .map_err(GluqloError::Render);
+        self.canvas.set_clip_rect(None);
+        drawn
+    }
+}
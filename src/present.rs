@@ -0,0 +1,319 @@
+//! Getting finished frames onto the screen.
+
+use gluqlo::{GluqloError, Result};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::surface::{Surface, SurfaceRef};
+use sdl2::sys::SDL_RendererFlags;
+use sdl2::video::{Window, WindowContext};
+use sdl2::EventPump;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// What a frame is drawn into.
+pub enum Target<'a> {
+    /// A surface in system memory, drawn by the CPU.
+    Surface(&'a mut SurfaceRef),
+    /// An SDL renderer, usually on the GPU, currently drawing into the frame.
+    Canvas(&'a mut WindowCanvas),
+}
+
+/// A window, with the SDL renderer that shows its frames unless they are drawn
+/// straight into the window surface.
+///
+/// Textures are made by the renderer's creator and must not outlive it, so
+/// the output is kept apart from the [`Presenter`] whose textures borrow it.
+pub enum Output {
+    /// Draw into the window surface.
+    Surface(Window),
+    /// Upload frames to a texture of a renderer that waits for the display.
+    Vsync {
+        canvas: WindowCanvas,
+        creator: TextureCreator<WindowContext>,
+    },
+    /// Draw with an accelerated renderer.
+    Accelerated {
+        canvas: WindowCanvas,
+        creator: TextureCreator<WindowContext>,
+        vsync: bool,
+    },
+}
+
+impl Output {
+    /// Present through the window surface, as fast as frames are drawn.
+    pub fn surface(window: Window) -> Output {
+        Output::Surface(window)
+    }
+
+    /// Present through an SDL renderer that waits for the vertical blank.
+    pub fn vsync(window: Window) -> Result<Output> {
+        let canvas = window
+            .into_canvas()
+            .present_vsync()
+            .build()
+            .map_err(|e| GluqloError::Sdl(format!("cannot create renderer: {}", e)))?;
+        let creator = canvas.texture_creator();
+        Ok(Output::Vsync { canvas, creator })
+    }
+
+    /// Present through an accelerated SDL renderer, waiting for the vertical
+    /// blank if `vsync` is set.
+    ///
+    /// Without a GPU, or when its renderer cannot draw into textures, the window
+    /// is handed back with the error so the caller can present in software.
+    pub fn accelerated(
+        window: Window,
+        vsync: bool,
+    ) -> std::result::Result<Output, (Window, GluqloError)> {
+        let context = window.context();
+        let builder = window.into_canvas().accelerated();
+        let builder = if vsync {
+            builder.present_vsync()
+        } else {
+            builder
+        };
+        // Building the renderer consumes the window even when it fails.
+        let recover = |error: String| {
+            (
+                unsafe { Window::from_ref(context.clone()) },
+                GluqloError::Sdl(error),
+            )
+        };
+        let canvas = builder
+            .build()
+            .map_err(|e| recover(format!("no accelerated renderer: {}", e)))?;
+
+        let info = canvas.info();
+        let accelerated = SDL_RendererFlags::SDL_RENDERER_ACCELERATED as u32;
+        if info.flags & accelerated == 0 || !canvas.render_target_supported() {
+            return Err(recover(format!(
+                "renderer {} cannot draw into textures on the GPU",
+                info.name
+            )));
+        }
+
+        let creator = canvas.texture_creator();
+        Ok(Output::Accelerated {
+            canvas,
+            creator,
+            vsync,
+        })
+    }
+}
+
+/// Shows frames in the window of an [`Output`], either by drawing into its
+/// surface or, with vsync, by uploading them to a streaming texture of an SDL
+/// renderer, or by drawing them with an accelerated SDL renderer.
+pub enum Presenter<'r> {
+    /// Draw straight into the window surface and copy it to the screen.
+    Surface(&'r Window),
+    /// Draw into `frame`, then upload and present it in step with the display.
+    Vsync {
+        canvas: &'r mut WindowCanvas,
+        creator: &'r TextureCreator<WindowContext>,
+        frame: Surface<'static>,
+        texture: Texture<'r>,
+    },
+    /// Draw with the GPU into the target texture `frame`, then present it.
+    Accelerated {
+        canvas: &'r mut WindowCanvas,
+        creator: &'r TextureCreator<WindowContext>,
+        frame: Texture<'r>,
+        vsync: bool,
+    },
+}
+
+fn texture_error(e: impl ToString) -> GluqloError {
+    GluqloError::Render(e.to_string())
+}
+
+impl<'r> Presenter<'r> {
+    /// Present through `output`, with frames the size of its window.
+    pub fn new(output: &'r mut Output) -> Result<Presenter<'r>> {
+        Ok(match output {
+            Output::Surface(window) => Presenter::Surface(window),
+            Output::Vsync { canvas, creator } => {
+                let (w, h) = canvas.window().size();
+                Presenter::Vsync {
+                    texture: creator
+                        .create_texture_streaming(PixelFormatEnum::ARGB8888, w, h)
+                        .map_err(texture_error)?,
+                    frame: Surface::new(w, h, PixelFormatEnum::ARGB8888)
+                        .map_err(GluqloError::Render)?,
+                    canvas,
+                    creator,
+                }
+            }
+            Output::Accelerated {
+                canvas,
+                creator,
+                vsync,
+            } => {
+                let (w, h) = canvas.window().size();
+                Presenter::Accelerated {
+                    frame: creator
+                        .create_texture_target(PixelFormatEnum::ARGB8888, w, h)
+                        .map_err(texture_error)?,
+                    canvas,
+                    creator,
+                    vsync: *vsync,
+                }
+            }
+        })
+    }
+
+    /// The texture creator of the SDL renderer frames are drawn with, if any.
+    pub fn texture_creator(&self) -> Option<&'r TextureCreator<WindowContext>> {
+        match self {
+            Presenter::Accelerated { creator, .. } => Some(creator),
+            Presenter::Surface(_) | Presenter::Vsync { .. } => None,
+        }
+    }
+
+    /// Let `draw` update the frame and show it.
+    ///
+    /// The frame keeps its contents between calls, so `draw` only has to paint
+    /// what changed.
+    pub fn present<F>(&mut self, event_pump: &EventPump, draw: F) -> Result<()>
+    where
+        F: FnOnce(Target) -> Result<()>,
+    {
+        match self {
+            Presenter::Surface(window) => {
+                let mut screen = window.surface(event_pump).map_err(GluqloError::Render)?;
+                draw(Target::Surface(&mut screen))?;
+                screen.finish().map_err(GluqloError::Render)
+            }
+            Presenter::Vsync {
+                canvas,
+                frame,
+                texture,
+                ..
+            } => {
+                draw(Target::Surface(frame))?;
+                let pixels = frame.without_lock().ok_or_else(|| {
+                    GluqloError::Render("cannot read the frame pixels".to_owned())
+                })?;
+                texture
+                    .update(None, pixels, frame.pitch() as usize)
+                    .map_err(texture_error)?;
+                canvas
+                    .copy(texture, None, None)
+                    .map_err(GluqloError::Render)?;
+                // Blocks until the next vertical blank.
+                canvas.present();
+                Ok(())
+            }
+            Presenter::Accelerated { canvas, frame, .. } => {
+                let mut drawn = Ok(());
+                canvas
+                    .with_texture_canvas(frame, |canvas| drawn = draw(Target::Canvas(canvas)))
+                    .map_err(texture_error)?;
+                drawn?;
+                canvas
+                    .copy(frame, None, None)
+                    .map_err(GluqloError::Render)?;
+                canvas.present();
+                Ok(())
+            }
+        }
+    }
+
+    /// The window frames are shown in.
+    pub fn window(&self) -> &Window {
+        match self {
+            Presenter::Surface(window) => window,
+            Presenter::Vsync { canvas, .. } | Presenter::Accelerated { canvas, .. } => {
+                canvas.window()
+            }
+        }
+    }
+
+    /// Make the frame match the size of the window again, after it changed.
+    ///
+    /// The new frame is blank, so everything has to be drawn again.
+    pub fn resize(&mut self) -> Result<()> {
+        let (w, h) = self.window().size();
+        match self {
+            Presenter::Surface(_) => {}
+            Presenter::Vsync {
+                creator,
+                frame,
+                texture,
+                ..
+            } => {
+                *texture = creator
+                    .create_texture_streaming(PixelFormatEnum::ARGB8888, w, h)
+                    .map_err(texture_error)?;
+                *frame =
+                    Surface::new(w, h, PixelFormatEnum::ARGB8888).map_err(GluqloError::Render)?;
+            }
+            Presenter::Accelerated { creator, frame, .. } => {
+                *frame = creator
+                    .create_texture_target(PixelFormatEnum::ARGB8888, w, h)
+                    .map_err(texture_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether presenting waits for the display.
+    pub fn is_vsync(&self) -> bool {
+        match self {
+            Presenter::Surface(_) => false,
+            Presenter::Vsync { .. } => true,
+            Presenter::Accelerated { vsync, .. } => *vsync,
+        }
+    }
+
+    /// Whether frames are drawn by the GPU.
+    pub fn is_accelerated(&self) -> bool {
+        matches!(self, Presenter::Accelerated { .. })
+    }
+}
+
+/// Timing of the frames of one animation, for `--debug`.
+#[derive(Debug)]
+pub struct FrameStats {
+    start: Instant,
+    frames: u32,
+    drawing: Duration,
+    slowest: Duration,
+    target_fps: u32,
+}
+
+impl FrameStats {
+    pub fn new(target_fps: u32) -> FrameStats {
+        FrameStats {
+            start: Instant::now(),
+            frames: 0,
+            drawing: Duration::ZERO,
+            slowest: Duration::ZERO,
+            target_fps,
+        }
+    }
+
+    /// Count a frame that took `drawing` to draw and present.
+    pub fn record(&mut self, drawing: Duration) {
+        self.frames += 1;
+        self.drawing += drawing;
+        self.slowest = self.slowest.max(drawing);
+    }
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.;
+        write!(
+            f,
+            "{} frames in {:.1} ms ({:.1} fps, target {}), drawing took {:.2} ms on average, {:.2} ms at most",
+            self.frames,
+            elapsed * 1000.,
+            self.frames as f64 / elapsed.max(f64::EPSILON),
+            self.target_fps,
+            ms(self.drawing) / self.frames.max(1) as f64,
+            ms(self.slowest)
+        )
+    }
+}
@@ -0,0 +1,853 @@
+use crate::draw::fill_rounded_box_b;
+use crate::paint::{CacheStats, CanvasPainter, Painter, SurfacePainter, Surfaces, TextureCache};
+use crate::transition::progress;
+use crate::{Animation, FontSource, GluqloError, Result, Theme};
+use chrono::{NaiveDate, NaiveTime, Timelike};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Canvas, RenderTarget};
+use sdl2::surface::{Surface, SurfaceRef};
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use std::cell::RefCell;
+use std::fmt::Write;
+
+/// Largest digit ink box as a fraction of the card size. The embedded font
+/// measures 0.443 x 0.762 at its nominal size.
+const DIGIT_FIT_WIDTH: f32 = 0.45;
+const DIGIT_FIT_HEIGHT: f32 = 0.77;
+/// Largest "AM"/"PM" label box as a fraction of the card size.
+const LABEL_FIT_WIDTH: f32 = 0.16;
+const LABEL_FIT_HEIGHT: f32 = 0.1;
+/// Card height as a fraction of the clock height, when the cards fit across.
+const CARD_HEIGHT: f32 = 0.6;
+/// Gap between cards, and the least margin at either side, as a fraction of the width.
+const CARD_SPACING: f32 = 0.031;
+
+/// A wall-clock time as shown on the cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Time {
+    /// Hour of the day, `0..24`.
+    pub hour: u32,
+    /// Minute of the hour, `0..60`.
+    pub minute: u32,
+    /// Second of the minute, `0..60`; only shown with [`Options::seconds`].
+    pub second: u32,
+    /// The day, for the date line of [`Options::date`].
+    pub date: Option<NaiveDate>,
+}
+
+impl Time {
+    pub fn new(hour: u32, minute: u32) -> Time {
+        Time {
+            hour,
+            minute,
+            second: 0,
+            date: None,
+        }
+    }
+
+    /// The same time at `second` past the minute.
+    pub fn with_second(self, second: u32) -> Time {
+        Time { second, ..self }
+    }
+
+    /// The same time on `date`.
+    pub fn with_date(self, date: NaiveDate) -> Time {
+        Time {
+            date: Some(date),
+            ..self
+        }
+    }
+}
+
+impl From<NaiveTime> for Time {
+    fn from(time: NaiveTime) -> Time {
+        Time::new(time.hour(), time.minute()).with_second(time.second())
+    }
+}
+
+/// Display options for [`FlipClockRenderer`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Show a 12-hour clock with an AM/PM label.
+    pub ampm: bool,
+    /// Pad single-digit hours with a zero.
+    pub leading_zero: bool,
+    /// Fraction of the target size used by the clock, the rest is left as margin.
+    pub display_scale_factor: f32,
+    /// How cards turn over to a new value. [`Animation::Instant`] draws no
+    /// divider either.
+    pub animation: Animation,
+    /// Add a third card with the seconds.
+    pub seconds: bool,
+    /// Font for the digits.
+    pub digit_font: FontSource,
+    /// Font for the AM/PM label.
+    pub label_font: FontSource,
+    /// Colors of the cards, digits, label and background.
+    pub theme: Theme,
+    /// Text drawn below the cards, such as the city of a world clock.
+    pub caption: Option<String>,
+    /// Draw the date below the cards, above any caption, written with this
+    /// `strftime` format such as `"%a %-d %b"`. Only times that carry a
+    /// [`Time::date`] show it.
+    pub date: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            ampm: false,
+            leading_zero: false,
+            display_scale_factor: 1.,
+            animation: Animation::Flip,
+            seconds: false,
+            digit_font: FontSource::Embedded,
+            label_font: FontSource::Embedded,
+            theme: Theme::CLASSIC,
+            caption: None,
+            date: None,
+        }
+    }
+}
+
+/// Draws the flip clock onto any SDL surface, or through an SDL renderer.
+///
+/// The renderer only knows the size of its target. It does not own a window or an
+/// event loop, so it can draw into a window surface, an offscreen buffer or a
+/// surface owned by another application. Several renderers with their own
+/// viewports can share one surface.
+pub struct FlipClockRenderer<'ttf> {
+    viewport: Rect,
+    /// The part of the viewport `display_scale_factor` leaves to the clock.
+    area: Rect,
+    /// How far the clock is moved from where it was laid out.
+    offset: Point,
+    hour_background: Rect,
+    min_background: Rect,
+    sec_background: Option<Rect>,
+    /// Digit spacing, scaled with the cards.
+    spc: i32,
+    divider_height: u32,
+    radius: i32,
+    pub(crate) bg: Surface<'static>,
+    pub(crate) font_time: Font<'ttf, 'static>,
+    font_mode: Font<'ttf, 'static>,
+    options: Options,
+    /// Glyphs and cards for [`FlipClockRenderer::render`].
+    surfaces: RefCell<Surfaces>,
+}
+
+impl<'ttf> FlipClockRenderer<'ttf> {
+    /// Compute the layout for a `w` x `h` target and load the fonts at matching sizes.
+    pub fn new(
+        ttf_context: &'ttf Sdl2TtfContext,
+        w: u32,
+        h: u32,
+        options: Options,
+    ) -> Result<FlipClockRenderer<'ttf>> {
+        FlipClockRenderer::with_viewport(ttf_context, Rect::new(0, 0, w, h), options)
+    }
+
+    /// Like [`FlipClockRenderer::new`], but lay the clock out in `viewport` of the
+    /// target instead of all of it.
+    pub fn with_viewport(
+        ttf_context: &'ttf Sdl2TtfContext,
+        viewport: Rect,
+        options: Options,
+    ) -> Result<FlipClockRenderer<'ttf>> {
+        let h = viewport.height();
+        let area = clock_area(viewport, options.display_scale_factor);
+        let (left, top, width, height) = (area.x(), area.y(), area.width(), area.height());
+
+        let cards = card_count(&options);
+        let spacing_f = CARD_SPACING * width as f32;
+        let fit = card_fit(&options, width, height);
+        // Everything sized from the height is sized from this instead.
+        let card_height = height as f32 * fit;
+
+        let rectsize = (card_height * CARD_HEIGHT) as u32;
+        let spacing = spacing_f as i32;
+        let radius = (card_height * 0.05714) as i32;
+
+        let font_time = options.digit_font.load_fitted(
+            ttf_context,
+            (card_height / 1.68) as u16,
+            (rectsize as f32 * DIGIT_FIT_WIDTH) as u32,
+            (rectsize as f32 * DIGIT_FIT_HEIGHT) as u32,
+            digit_ink_size,
+        )?;
+        let font_mode = options.label_font.load_fitted(
+            ttf_context,
+            (card_height / 16.5) as u16,
+            (rectsize as f32 * LABEL_FIT_WIDTH) as u32,
+            (rectsize as f32 * LABEL_FIT_HEIGHT) as u32,
+            label_size,
+        )?;
+
+        let hour_background = Rect::new(
+            (0.5 * (width as f32
+                - ((cards - 1) as f32 * spacing_f)
+                - (CARD_HEIGHT * cards as f32 * card_height))) as i32
+                + left,
+            // Centered: 0.2 of the height for full-size cards.
+            (0.2 * height as f32 + 0.3 * (height as f32 - card_height)) as i32 + top,
+            rectsize,
+            rectsize,
+        );
+
+        let card_step = (CARD_HEIGHT * card_height) as i32 + spacing;
+        let min_background = Rect::new(
+            hour_background.x() + card_step,
+            hour_background.y(),
+            rectsize,
+            rectsize,
+        );
+        let sec_background = if options.seconds {
+            Some(Rect::new(
+                min_background.x() + card_step,
+                min_background.y(),
+                rectsize,
+                rectsize,
+            ))
+        } else {
+            None
+        };
+
+        let spc = (h as f32 * 0.0125 * fit) as i32;
+        let divider_height = (h as f32 * 0.005 * fit) as u32;
+
+        // dbg!(PixelFormatEnum::RGB24.into_masks());
+        let bg = card_surface(rectsize, radius, options.theme.card)?;
+
+        Ok(FlipClockRenderer {
+            viewport,
+            area,
+            offset: Point::new(0, 0),
+            hour_background,
+            min_background,
+            sec_background,
+            spc,
+            divider_height,
+            radius,
+            bg,
+            font_time,
+            font_mode,
+            options,
+            surfaces: RefCell::default(),
+        })
+    }
+
+    /// Lay the clock out again in `viewport`, after the target was resized.
+    ///
+    /// The fonts are loaded at the new size and the card is drawn again, and
+    /// the caches start over. The offset is kept as far as the new margin
+    /// allows.
+    pub fn relayout(&mut self, ttf_context: &'ttf Sdl2TtfContext, viewport: Rect) -> Result<()> {
+        let offset = self.offset;
+        *self = FlipClockRenderer::with_viewport(ttf_context, viewport, self.options.clone())?;
+        self.set_offset(offset);
+        Ok(())
+    }
+
+    /// The part of the target the clock is laid out in.
+    pub fn viewport(&self) -> Rect {
+        self.viewport
+    }
+
+    /// How far the clock is moved from where it was laid out.
+    pub fn offset(&self) -> Point {
+        self.offset
+    }
+
+    /// The least and the greatest offset for [`FlipClockRenderer::set_offset`]
+    /// that keep the clock inside its viewport: the margin left by
+    /// `display_scale_factor`, the same either way so the clock drifts around
+    /// the center.
+    pub fn offset_range(&self) -> (Point, Point) {
+        offset_range_in(self.viewport, self.area)
+    }
+
+    /// Move the clock to `offset` from where it was laid out, within
+    /// [`FlipClockRenderer::offset_range`], to protect the display from burn-in.
+    ///
+    /// Nothing is drawn; clear the viewport and draw every card again, since
+    /// the clock at the old position is still on screen.
+    pub fn set_offset(&mut self, offset: Point) {
+        let (min, max) = self.offset_range();
+        let offset = Point::new(
+            offset.x().clamp(min.x(), max.x()),
+            offset.y().clamp(min.y(), max.y()),
+        );
+        let (dx, dy) = (offset.x() - self.offset.x(), offset.y() - self.offset.y());
+        self.hour_background.offset(dx, dy);
+        self.min_background.offset(dx, dy);
+        if let Some(card) = &mut self.sec_background {
+            card.offset(dx, dy);
+        }
+        self.offset = offset;
+    }
+
+    /// Split a `w` x `h` target into one viewport per clock for `count` clocks.
+    ///
+    /// The grid gets as many columns as make the cards largest, so wide screens
+    /// put clocks side by side and tall ones stack them.
+    pub fn grid(options: &Options, count: u32, w: u32, h: u32) -> Vec<Rect> {
+        let count = count.max(1);
+        let columns = (1..=count)
+            .max_by_key(|&columns| {
+                let rows = count.div_ceil(columns);
+                let (width, height) = (w / columns, h / rows);
+                (card_fit(options, width, height) * height as f32) as u32
+            })
+            .unwrap_or(1);
+        let rows = count.div_ceil(columns);
+        let (width, height) = (w / columns, h / rows);
+
+        (0..count)
+            .map(|i| {
+                let (column, row) = (i % columns, i / columns);
+                // Center a short last row.
+                let in_row = if row == rows - 1 {
+                    count - row * columns
+                } else {
+                    columns
+                };
+                let x = ((w - in_row * width) / 2 + column * width) as i32;
+                Rect::new(x, (row * height) as i32, width, height)
+            })
+            .collect()
+    }
+
+    /// Options the renderer was created with.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Switch to `theme`, dropping everything drawn in the old colors.
+    ///
+    /// The cards on screen keep the old theme until they are drawn again.
+    pub fn set_theme(&mut self, theme: Theme) -> Result<()> {
+        self.bg = card_surface(self.bg.height(), self.radius, theme.card)?;
+        self.options.theme = theme;
+        self.surfaces.get_mut().clear();
+        Ok(())
+    }
+
+    /// How well the glyph and card caches of [`FlipClockRenderer::render`] work.
+    /// The textures of [`FlipClockRenderer::render_canvas`] are counted by
+    /// [`TextureCache::stats`].
+    pub fn cache_stats(&self) -> CacheStats {
+        self.surfaces.borrow().stats()
+    }
+
+    /// Fill the whole `surface` with the theme background.
+    pub fn clear(&self, surface: &mut SurfaceRef) -> Result<()> {
+        self.with_surface(surface, |painter| {
+            painter.fill(None, self.options.theme.background)
+        })
+    }
+
+    /// Like [`FlipClockRenderer::clear`], through an SDL renderer.
+    pub fn clear_canvas<T: RenderTarget>(&self, canvas: &mut Canvas<T>) -> Result<()> {
+        canvas.set_blend_mode(BlendMode::None);
+        canvas.set_draw_color(self.options.theme.background);
+        canvas.fill_rect(None).map_err(GluqloError::Render)
+    }
+
+    /// Draw `step` of `maxsteps` of the flip from `previous` to `time`.
+    ///
+    /// Only cards whose value differs from `previous` are drawn, and the date line
+    /// only when the date does; pass `None` to draw every card, the date and the
+    /// caption. The last step, `maxsteps - 1`, shows `time` fully flipped.
+    pub fn render(
+        &self,
+        surface: &mut SurfaceRef,
+        time: Time,
+        previous: Option<Time>,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()> {
+        self.with_surface(surface, |painter| {
+            self.paint(painter, time, previous, maxsteps, step)
+        })
+    }
+
+    /// Write `text` across the top of the viewport, over whatever was there,
+    /// for debugging output such as [`FlipClockRenderer::cache_stats`].
+    pub fn render_overlay(&self, surface: &mut SurfaceRef, text: &str) -> Result<()> {
+        self.with_surface(surface, |painter| self.paint_overlay(painter, text))
+    }
+
+    /// Like [`FlipClockRenderer::render_overlay`], through an SDL renderer.
+    pub fn render_overlay_canvas<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        textures: &mut TextureCache<T::Context>,
+        text: &str,
+    ) -> Result<()> {
+        self.with_canvas(canvas, textures, |painter| {
+            self.paint_overlay(painter, text)
+        })
+    }
+
+    /// Like [`FlipClockRenderer::render`], but draw through an SDL renderer, which
+    /// is usually backed by the GPU.
+    ///
+    /// The card and the glyphs are uploaded to `textures` once and the flap is
+    /// squashed as a textured quad. `textures` has to be made from the
+    /// texture creator of `canvas`.
+    pub fn render_canvas<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        textures: &mut TextureCache<T::Context>,
+        time: Time,
+        previous: Option<Time>,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()> {
+        self.with_canvas(canvas, textures, |painter| {
+            self.paint(painter, time, previous, maxsteps, step)
+        })
+    }
+
+    fn with_surface(
+        &self,
+        surface: &mut SurfaceRef,
+        draw: impl FnOnce(&mut SurfacePainter) -> Result<()>,
+    ) -> Result<()> {
+        let mut cache = self.surfaces.borrow_mut();
+        draw(&mut SurfacePainter {
+            surface,
+            cache: &mut cache,
+        })
+    }
+
+    fn with_canvas<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        textures: &mut TextureCache<T::Context>,
+        draw: impl FnOnce(&mut CanvasPainter<T>) -> Result<()>,
+    ) -> Result<()> {
+        draw(&mut CanvasPainter { canvas, textures })
+    }
+
+    fn paint<P: Painter>(
+        &self,
+        painter: &mut P,
+        time: Time,
+        previous: Option<Time>,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()> {
+        if previous.is_none_or(|p| p.hour != time.hour) {
+            let buffer = self.format_hour(time.hour);
+            let buffer2 = previous.map_or_else(|| buffer.clone(), |p| self.format_hour(p.hour));
+
+            self.render_digits(
+                painter,
+                self.hour_background,
+                &buffer,
+                &buffer2,
+                maxsteps,
+                step,
+            )?;
+            if self.options.ampm {
+                self.render_ampm(painter, &self.hour_background, time.hour >= 12)?;
+            }
+        }
+
+        if previous.is_none_or(|p| p.minute != time.minute) {
+            let buffer = format!("{:02}", time.minute);
+            let buffer2 = previous.map_or_else(|| buffer.clone(), |p| format!("{:02}", p.minute));
+            self.render_digits(
+                painter,
+                self.min_background,
+                &buffer,
+                &buffer2,
+                maxsteps,
+                step,
+            )?;
+        }
+
+        if previous.is_none() {
+            self.render_caption(painter)?;
+        }
+
+        if let Some(date) = time.date {
+            if previous.is_none_or(|p| p.date != time.date) {
+                let before = previous.and_then(|p| p.date);
+                self.render_date(painter, date, before, maxsteps, step)?;
+            }
+        }
+
+        if let Some(sec_background) = self.sec_background {
+            if previous.is_none_or(|p| p.second != time.second) {
+                let buffer = format!("{:02}", time.second);
+                let buffer2 =
+                    previous.map_or_else(|| buffer.clone(), |p| format!("{:02}", p.second));
+                self.render_digits(painter, sec_background, &buffer, &buffer2, maxsteps, step)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn paint_overlay<P: Painter>(&self, painter: &mut P, text: &str) -> Result<()> {
+        let line = Rect::new(
+            self.viewport.x(),
+            self.viewport.y(),
+            self.viewport.width(),
+            self.font_mode.height().max(1) as u32,
+        );
+        painter.fill(Some(line), self.options.theme.background)?;
+        if text.is_empty() {
+            return Ok(());
+        }
+        painter.text(
+            &self.font_mode,
+            text,
+            self.options.theme.label,
+            &|_, _| Point::new(line.x() + self.spc, line.y()),
+            Some(line),
+        )
+    }
+
+    fn format_hour(&self, hour: u32) -> String {
+        let h = if self.options.ampm {
+            (hour + 11) % 12 + 1
+        } else {
+            hour
+        };
+        if self.options.leading_zero {
+            format!("{:02}", h)
+        } else {
+            format!("{}", h)
+        }
+    }
+
+    /// The rows for the date line and the caption: the space between the bottom
+    /// of the cards and the bottom of the viewport, split in two if there are both.
+    fn rows_below(&self) -> (Rect, Rect) {
+        let top = self.hour_background.bottom();
+        let space = self.viewport.bottom() - top;
+        let row = |y: i32, height: i32| {
+            Rect::new(
+                self.viewport.x(),
+                y,
+                self.viewport.width(),
+                height.max(0) as u32,
+            )
+        };
+        let captioned = self
+            .options
+            .caption
+            .as_deref()
+            .is_some_and(|caption| !caption.is_empty());
+        if self.options.date.is_some() && captioned {
+            let date_height = space / 2;
+            (
+                row(top, date_height),
+                row(top + date_height, space - date_height),
+            )
+        } else {
+            (row(top, space), row(top, space))
+        }
+    }
+
+    /// Draw `text` centered in `row`, which is cleared first.
+    fn paint_row<P: Painter>(
+        &self,
+        painter: &mut P,
+        row: Rect,
+        lines: &[(&str, Color)],
+    ) -> Result<()> {
+        painter.fill(Some(row), self.options.theme.background)?;
+        // SDL_ttf cannot render empty text.
+        for &(text, color) in lines.iter().filter(|(text, _)| !text.is_empty()) {
+            painter.text(
+                &self.font_mode,
+                text,
+                color,
+                &|w, h| {
+                    Point::new(
+                        row.x() + self.offset.x() + (row.width() as i32 - w as i32) / 2,
+                        row.y() + (row.height() as i32 - h as i32) / 2,
+                    )
+                },
+                Some(row),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn render_caption<P: Painter>(&self, painter: &mut P) -> Result<()> {
+        let caption = match self.options.caption.as_deref() {
+            Some(caption) if !caption.is_empty() => caption,
+            _ => return Ok(()),
+        };
+        let (_, row) = self.rows_below();
+        self.paint_row(painter, row, &[(caption, self.options.theme.label)])
+    }
+
+    /// Draw the date line for `date`, fading over from `previous` at midnight.
+    fn render_date<P: Painter>(
+        &self,
+        painter: &mut P,
+        date: NaiveDate,
+        previous: Option<NaiveDate>,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()> {
+        let format = match self.options.date.as_deref() {
+            Some(format) => format,
+            None => return Ok(()),
+        };
+        let (row, _) = self.rows_below();
+        let theme = &self.options.theme;
+        let text = format_date(date, format)?;
+
+        let previous = previous
+            .filter(|&previous| previous != date && self.options.animation != Animation::Instant);
+        match previous {
+            // The old date fades out while the new one fades in.
+            Some(previous) => {
+                let t = progress(maxsteps, step) as f32;
+                let old = format_date(previous, format)?;
+                self.paint_row(
+                    painter,
+                    row,
+                    &[
+                        (&old, theme.fade_label(1. - t)),
+                        (&text, theme.fade_label(t)),
+                    ],
+                )
+            }
+            None => self.paint_row(painter, row, &[(&text, theme.label)]),
+        }
+    }
+
+    fn render_ampm<P: Painter>(&self, painter: &mut P, rect: &Rect, pm: bool) -> Result<()> {
+        let mode = format!("{}M", if pm { "P" } else { "A" });
+
+        let offset = (rect.height() as f32 * 0.127) as i32;
+        painter.text(
+            &self.font_mode,
+            &mode,
+            self.options.theme.label,
+            &|_, h| {
+                Point::new(
+                    rect.x() + (rect.height() as f32 * 0.07) as i32,
+                    rect.y()
+                        + if pm {
+                            rect.height() as i32 - offset - h as i32
+                        } else {
+                            offset
+                        },
+                )
+            },
+            None,
+        )
+    }
+
+    /// Where the glyphs of `digits`, rendered at `sizes`, go on the card at `rect`.
+    ///
+    /// Two digits sit either side of the middle, a single one is centered, and a
+    /// leading `1` is nudged left so it doesn't look lost.
+    pub(crate) fn place_digits(
+        &self,
+        rect: Rect,
+        digits: &str,
+        sizes: &[(u32, u32)],
+    ) -> Result<Vec<Point>> {
+        let spc = self.spc;
+        let adjust_x = if digits.starts_with('1') {
+            (2.5 * spc as f32) as i32
+        } else {
+            0
+        };
+        let center_x = rect.x() + rect.width() as i32 / 2 - adjust_x;
+        let middle = |height: u32| rect.y() + (rect.height() as i32 - height as i32) / 2;
+
+        if sizes.len() > 1 {
+            let glyph_metrics = self
+                .font_time
+                .find_glyph_metrics(digits.chars().nth(1).unwrap())
+                .ok_or_else(|| GluqloError::Render(format!("no glyph for {:?}", digits)))?;
+            Ok(vec![
+                Point::new(
+                    center_x - glyph_metrics.maxx + glyph_metrics.minx
+                        - spc
+                        - if adjust_x > 0 { spc } else { 0 },
+                    middle(sizes[0].1),
+                ),
+                Point::new(center_x + spc / 2, middle(sizes[1].1)),
+            ])
+        } else {
+            Ok(vec![Point::new(
+                center_x - sizes[0].0 as i32 / 2,
+                middle(sizes[0].1),
+            )])
+        }
+    }
+
+    fn render_digits<P: Painter>(
+        &self,
+        painter: &mut P,
+        background: Rect,
+        digits: &str,
+        prevdigits: &str,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()> {
+        self.options.animation.transition().draw(
+            self, painter, background, digits, prevdigits, maxsteps, step,
+        )?;
+
+        if self.options.animation == Animation::Instant {
+            return Ok(());
+        }
+
+        // Draw divider
+        let mut rect = Rect::new(
+            background.x(),
+            background.y() + (background.height() as i32 - self.divider_height as i32) / 2,
+            background.width(),
+            self.divider_height,
+        );
+        painter.fill(Some(rect), self.options.theme.divider)?;
+        rect.set_y(rect.y() + rect.height() as i32);
+        rect.set_height(1);
+        painter.fill(Some(rect), self.options.theme.divider_highlight)
+    }
+}
+
+/// `date` written with the `strftime` `format`.
+fn format_date(date: NaiveDate, format: &str) -> Result<String> {
+    let mut text = String::new();
+    write!(text, "{}", date.format(format))
+        .map_err(|_| GluqloError::Render(format!("cannot write a date as {:?}", format)))?;
+    Ok(text)
+}
+
+/// A blank `size` x `size` card with corners of radius `radius`.
+fn card_surface(size: u32, radius: i32, color: Color) -> Result<Surface<'static>> {
+    let mut bg = Surface::new(size, size, PixelFormatEnum::RGBA32).map_err(GluqloError::Render)?;
+    fill_rounded_box_b(&mut bg, &Rect::new(0, 0, size, size), radius, color);
+    Ok(bg)
+}
+
+/// The part of `viewport` the clock is laid out in at `scale` of its size,
+/// centered when it is smaller.
+fn clock_area(viewport: Rect, scale: f32) -> Rect {
+    let (w, h) = (viewport.width(), viewport.height());
+    let width = (w as f32 * scale) as u32;
+    let height = (h as f32 * scale) as u32;
+    let (mut left, mut top) = (1, 1);
+    if (1. - scale) > f32::EPSILON {
+        left = ((w - width) / 2) as i32;
+        top = ((h - height) / 2) as i32;
+    }
+    Rect::new(viewport.x() + left, viewport.y() + top, width, height)
+}
+
+/// Offsets that keep `area` inside `viewport`, as far one way as the other.
+fn offset_range_in(viewport: Rect, area: Rect) -> (Point, Point) {
+    let margin = |before: i32, after: i32| before.min(after).max(0);
+    let x = margin(
+        area.left() - viewport.left(),
+        viewport.right() - area.right(),
+    );
+    let y = margin(
+        area.top() - viewport.top(),
+        viewport.bottom() - area.bottom(),
+    );
+    (Point::new(-x, -y), Point::new(x, y))
+}
+
+/// Number of cards side by side.
+fn card_count(options: &Options) -> u32 {
+    if options.seconds {
+        3
+    } else {
+        2
+    }
+}
+
+/// How much the cards shrink from their classic size of 0.6 of the height.
+///
+/// Cards keep that size unless they would not fit across the width, which
+/// happens in narrow windows, in world clock cells and with the seconds card.
+fn card_fit(options: &Options, width: u32, height: u32) -> f32 {
+    let cards = card_count(options);
+    let spacing = CARD_SPACING * width as f32;
+    let fit = (width as f32 - (cards + 1) as f32 * spacing)
+        / (CARD_HEIGHT * cards as f32 * height as f32);
+    fit.clamp(0., 1.)
+}
+
+/// Largest ink width and height over the digits `0`-`9`.
+fn digit_ink_size(font: &Font) -> (u32, u32) {
+    ('0'..='9')
+        .filter_map(|c| font.find_glyph_metrics(c))
+        .fold((0, 0), |(w, h), m| {
+            (
+                w.max((m.maxx - m.minx).max(0) as u32),
+                h.max((m.maxy - m.miny).max(0) as u32),
+            )
+        })
+}
+
+/// Size of the wider of the rendered "AM" and "PM" labels.
+fn label_size(font: &Font) -> (u32, u32) {
+    ["AM", "PM"]
+        .iter()
+        .filter_map(|text| font.size_of(text).ok())
+        .fold((0, 0), |(w, h), (tw, th)| (w.max(tw), h.max(th)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn wide_digit_fonts_are_shrunk_to_fit_the_card() {
+        let ttf_context = sdl2::ttf::init().unwrap();
+        // Its digits are 0.5 of the card wide at the size the cards ask for.
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fonts/DejaVuSansMono-Bold.ttf");
+        let font = FontSource::file(&path, 0).unwrap();
+        let options = Options {
+            digit_font: font.clone(),
+            ..Options::default()
+        };
+        let clock = FlipClockRenderer::new(&ttf_context, 640, 480, options).unwrap();
+
+        let card = clock.hour_background;
+        let (width, height) = digit_ink_size(&clock.font_time);
+        assert!(width as f32 <= card.width() as f32 * DIGIT_FIT_WIDTH);
+        assert!(height as f32 <= card.height() as f32 * DIGIT_FIT_HEIGHT);
+
+        let nominal = (card.height() as f32 / CARD_HEIGHT / 1.68) as u16;
+        let unfitted = font.load(&ttf_context, nominal).unwrap();
+        assert!(digit_ink_size(&unfitted).0 as f32 > card.width() as f32 * DIGIT_FIT_WIDTH);
+    }
+
+    #[test]
+    fn scaled_clocks_are_centered_and_drift_evenly() {
+        let viewport = Rect::new(100, 50, 641, 480);
+        let area = clock_area(viewport, 0.8);
+        assert_eq!(area, Rect::new(164, 98, 512, 384));
+
+        let (min, max) = offset_range_in(viewport, area);
+        assert_eq!(min, -max);
+        assert_eq!(max, Point::new(64, 48));
+
+        let full = clock_area(viewport, 1.);
+        assert_eq!(
+            offset_range_in(viewport, full),
+            (Point::new(0, 0), Point::new(0, 0))
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use std::time::Instant;
+
+/// Longest real time between two wake-ups, so a clock that jumped is noticed soon
+/// even while the next flip is far away.
+const MAX_WAIT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Largest difference, in real seconds, between how far the clock moved and how
+/// far it should have moved before it counts as a jump.
+const JUMP_TOLERANCE: f64 = 1.;
+
+/// Decides when flips start so that they end exactly on the minute (or second).
+///
+/// A flip takes `lead` to animate, so it starts `lead` before the boundary and
+/// already shows the time at the boundary. All of this is worked out on the
+/// clock's own time; [`Scheduler::wait`] converts it into real time to sleep.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    period: Duration,
+    lead: Duration,
+    last_wake: Option<(Instant, DateTime<Utc>)>,
+}
+
+impl Scheduler {
+    /// Flip every `period` of clock time, with flips taking `lead` of clock time
+    /// to animate.
+    pub fn new(period: std::time::Duration, lead: std::time::Duration) -> Scheduler {
+        let period = Duration::from_std(period).unwrap_or_else(|_| Duration::minutes(1));
+        let lead = Duration::from_std(lead).unwrap_or_else(|_| Duration::zero());
+        Scheduler {
+            period,
+            // A flip longer than the period would never catch up.
+            lead: lead.min(period),
+            last_wake: None,
+        }
+    }
+
+    /// Flip every minute, or every second with the seconds card.
+    pub fn for_clock(seconds: bool, lead: std::time::Duration) -> Scheduler {
+        let period = if seconds { 1 } else { 60 };
+        Scheduler::new(std::time::Duration::from_secs(period), lead)
+    }
+
+    /// The instant whose time a flip starting at `now` should land on.
+    pub fn target(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let ahead = now + self.lead;
+        ahead.duration_trunc(self.period).unwrap_or(ahead)
+    }
+
+    /// Clock time from `now` until the next flip has to start.
+    pub fn until_next_flip(&self, now: DateTime<Utc>) -> Duration {
+        self.target(now) + self.period - self.lead - now
+    }
+
+    /// Real time to sleep from `now` on a clock running `speed` times as fast as
+    /// real time: until the next flip, but never longer than a second.
+    pub fn wait(&self, now: DateTime<Utc>, speed: f64) -> std::time::Duration {
+        let clock = self
+            .until_next_flip(now)
+            .to_std()
+            .unwrap_or(std::time::Duration::ZERO);
+        clock
+            .div_f64(speed)
+            .clamp(std::time::Duration::from_millis(1), MAX_WAIT)
+    }
+
+    /// Record a wake-up at real time `real` when the clock read `now`, and tell
+    /// whether the clock jumped since the previous one: stepped by NTP or the
+    /// user, or the machine was suspended.
+    pub fn jumped(&mut self, real: Instant, now: DateTime<Utc>, speed: f64) -> bool {
+        let jumped = match self.last_wake {
+            Some((last_real, last_now)) => {
+                let expected = real.saturating_duration_since(last_real).as_secs_f64() * speed;
+                let moved = (now - last_now).num_milliseconds() as f64 / 1000.;
+                (moved - expected).abs() > JUMP_TOLERANCE * speed
+            }
+            None => false,
+        };
+        self.last_wake = Some((real, now));
+        jumped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const LEAD: std::time::Duration = std::time::Duration::from_millis(260);
+
+    fn at(minute: u32, second: u32, milli: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2021, 7, 1, 11, minute, second)
+            .unwrap()
+            + Duration::milliseconds(milli)
+    }
+
+    #[test]
+    fn flips_start_early_enough_to_land_on_the_minute() {
+        let scheduler = Scheduler::for_clock(false, LEAD);
+        assert_eq!(scheduler.target(at(58, 59, 739)), at(58, 0, 0));
+        assert_eq!(scheduler.target(at(58, 59, 740)), at(59, 0, 0));
+        assert_eq!(
+            scheduler.until_next_flip(at(58, 30, 0)),
+            Duration::milliseconds(29_740)
+        );
+        // Right after a flip started the next one is a whole minute away.
+        assert_eq!(
+            scheduler.until_next_flip(at(58, 59, 740)),
+            Duration::minutes(1)
+        );
+    }
+
+    #[test]
+    fn seconds_flip_every_second() {
+        let scheduler = Scheduler::for_clock(true, LEAD);
+        assert_eq!(scheduler.target(at(58, 12, 740)), at(58, 13, 0));
+        assert_eq!(
+            scheduler.until_next_flip(at(58, 12, 100)),
+            Duration::milliseconds(640)
+        );
+    }
+
+    #[test]
+    fn waits_are_capped_and_scaled_to_real_time() {
+        let scheduler = Scheduler::for_clock(false, LEAD);
+        assert_eq!(scheduler.wait(at(58, 0, 0), 1.), MAX_WAIT);
+        assert_eq!(
+            scheduler.wait(at(58, 59, 540), 1.),
+            std::time::Duration::from_millis(200)
+        );
+        assert_eq!(
+            scheduler.wait(at(58, 59, 540), 2.),
+            std::time::Duration::from_millis(100)
+        );
+    }
+
+    #[test]
+    fn notices_jumps_of_the_clock() {
+        let mut scheduler = Scheduler::for_clock(false, LEAD);
+        let start = Instant::now();
+        assert!(!scheduler.jumped(start, at(58, 0, 0), 1.));
+        let second = std::time::Duration::from_secs(1);
+        assert!(!scheduler.jumped(start + second, at(58, 1, 0), 1.));
+        // An hour went by on the clock in one real second.
+        assert!(scheduler.jumped(start + 2 * second, at(58, 2, 0) + Duration::hours(1), 1.));
+        // A minute per second is no jump at 60 times speed.
+        assert!(!scheduler.jumped(start + 3 * second, at(59, 2, 0) + Duration::hours(1), 60.));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::Time;
+use std::sync::Mutex;
+
+/// The time a clock instance currently shows.
+///
+/// Each clock owns its own state, so several clocks can run in one process. It is
+/// `Sync` so a clock can share it (through an `Arc`) with a timer callback running
+/// on another thread.
+#[derive(Debug, Default)]
+pub struct ClockState {
+    displayed: Mutex<Option<Time>>,
+}
+
+impl ClockState {
+    pub fn new() -> ClockState {
+        ClockState::default()
+    }
+
+    /// The time on screen, or `None` before the first complete frame.
+    pub fn displayed(&self) -> Option<Time> {
+        *self.lock()
+    }
+
+    /// Record that `time` is now fully shown.
+    pub fn set_displayed(&self, time: Time) {
+        *self.lock() = Some(time);
+    }
+
+    /// Forget the shown time so the next frame redraws every card.
+    pub fn reset(&self) {
+        *self.lock() = None;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Time>> {
+        // The guarded value is a plain `Copy` time, a panic elsewhere cannot leave it torn.
+        self.displayed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{GluqloError, Result};
+use sdl2::pixels::Color;
+use std::path::Path;
+
+const DEFAULT_A: u8 = 0xff;
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color {
+        r,
+        g,
+        b,
+        a: DEFAULT_A,
+    }
+}
+
+/// Colors of every part of the clock.
+///
+/// Alpha is honored wherever SDL blends: the digits, the label and the card are
+/// composited over what is below them, while the divider and the window background
+/// replace the pixels they cover.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Digits on the cards.
+    pub digits: Color,
+    /// The AM/PM label.
+    pub label: Color,
+    /// The rounded cards.
+    pub card: Color,
+    /// The gap across the middle of each card.
+    pub divider: Color,
+    /// The one pixel line below the divider.
+    pub divider_highlight: Color,
+    /// Everything around the cards.
+    pub background: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::CLASSIC
+    }
+}
+
+impl Theme {
+    /// Light gray digits on near-black cards, the original Gluqlo look.
+    pub const CLASSIC: Theme = Theme {
+        digits: rgb(0xb7, 0xb7, 0xb7),
+        label: rgb(0xb7, 0xb7, 0xb7),
+        card: rgb(0x0f, 0x0f, 0x0f),
+        divider: rgb(0x00, 0x00, 0x00),
+        divider_highlight: rgb(0x1a, 0x1a, 0x1a),
+        background: rgb(0x00, 0x00, 0x00),
+    };
+
+    /// Dark digits on white cards.
+    pub const LIGHT: Theme = Theme {
+        digits: rgb(0x30, 0x30, 0x30),
+        label: rgb(0x30, 0x30, 0x30),
+        card: rgb(0xfa, 0xfa, 0xfa),
+        divider: rgb(0xc8, 0xc8, 0xc8),
+        divider_highlight: rgb(0xff, 0xff, 0xff),
+        background: rgb(0xe8, 0xe8, 0xe8),
+    };
+
+    /// Amber digits, like an old terminal.
+    pub const AMBER: Theme = Theme {
+        digits: rgb(0xff, 0xb0, 0x00),
+        label: rgb(0xff, 0xb0, 0x00),
+        card: rgb(0x1a, 0x10, 0x00),
+        divider: rgb(0x00, 0x00, 0x00),
+        divider_highlight: rgb(0x2a, 0x1c, 0x00),
+        background: rgb(0x00, 0x00, 0x00),
+    };
+
+    /// White digits on black.
+    pub const HIGH_CONTRAST: Theme = Theme {
+        digits: rgb(0xff, 0xff, 0xff),
+        label: rgb(0xff, 0xff, 0xff),
+        card: rgb(0x12, 0x12, 0x12),
+        divider: rgb(0x00, 0x00, 0x00),
+        divider_highlight: rgb(0x60, 0x60, 0x60),
+        background: rgb(0x00, 0x00, 0x00),
+    };
+
+    /// Dim red digits on black cards, easy on eyes used to the dark.
+    pub const NIGHT: Theme = Theme {
+        digits: rgb(0x70, 0x18, 0x10),
+        label: rgb(0x70, 0x18, 0x10),
+        card: rgb(0x08, 0x04, 0x04),
+        divider: rgb(0x00, 0x00, 0x00),
+        divider_highlight: rgb(0x10, 0x08, 0x08),
+        background: rgb(0x00, 0x00, 0x00),
+    };
+
+    /// Names accepted by [`Theme::builtin`].
+    pub const BUILTIN_NAMES: &'static [&'static str] =
+        &["classic", "light", "amber", "high-contrast", "night"];
+
+    /// Keys accepted by [`Theme::set`] and in theme files.
+    pub const KEYS: &'static [&'static str] = &[
+        "digits",
+        "label",
+        "card",
+        "divider",
+        "divider-highlight",
+        "background",
+    ];
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "classic" => Some(Theme::CLASSIC),
+            "light" => Some(Theme::LIGHT),
+            "amber" => Some(Theme::AMBER),
+            "high-contrast" => Some(Theme::HIGH_CONTRAST),
+            "night" => Some(Theme::NIGHT),
+            _ => None,
+        }
+    }
+
+    /// A built-in theme by name, or else a theme file at that path.
+    pub fn load(name_or_path: &str) -> Result<Theme> {
+        match Theme::builtin(name_or_path) {
+            Some(theme) => Ok(theme),
+            None => Theme::from_file(name_or_path),
+        }
+    }
+
+    /// Read a TOML theme file.
+    ///
+    /// Every key is optional and given as `"#rrggbb"` or `"#rrggbbaa"`; missing
+    /// colors come from the built-in theme named by `base`, `classic` by default:
+    ///
+    /// ```toml
+    /// base = "amber"
+    /// card = "#1a100080"
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Theme> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            GluqloError::Config(format!("cannot read theme {}: {}", path.display(), e))
+        })?;
+        Theme::from_toml(&text)
+            .map_err(|e| GluqloError::Config(format!("theme {}: {}", path.display(), e)))
+    }
+
+    fn from_toml(text: &str) -> std::result::Result<Theme, String> {
+        let table: toml::value::Table = toml::from_str(text).map_err(|e| e.to_string())?;
+
+        let mut theme = match table.get("base") {
+            None => Theme::CLASSIC,
+            Some(toml::Value::String(base)) => {
+                Theme::builtin(base).ok_or_else(|| format!("unknown base theme {:?}", base))?
+            }
+            Some(_) => return Err("`base` must be a theme name".to_owned()),
+        };
+
+        for (key, value) in &table {
+            if key == "base" {
+                continue;
+            }
+            let value = value
+                .as_str()
+                .ok_or_else(|| format!("`{}` must be a color string", key))?;
+            theme.set(key, value).map_err(|e| e.to_string())?;
+        }
+        Ok(theme)
+    }
+
+    /// Set the color for `key` (one of [`Theme::KEYS`]) from `"#rrggbb[aa]"`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let color = parse_color(value)?;
+        let slot = match key {
+            "digits" => &mut self.digits,
+            "label" => &mut self.label,
+            "card" => &mut self.card,
+            "divider" => &mut self.divider,
+            "divider-highlight" => &mut self.divider_highlight,
+            "background" => &mut self.background,
+            _ => {
+                return Err(GluqloError::Config(format!(
+                    "unknown theme color {:?}, expected one of {}",
+                    key,
+                    Theme::KEYS.join(", ")
+                )))
+            }
+        };
+        *slot = color;
+        Ok(())
+    }
+
+    /// Digit color at `visibility` from 0 (blended into the card) to 1 (fully shown).
+    pub fn fade(&self, visibility: f32) -> Color {
+        fade(self.card, self.digits, visibility)
+    }
+
+    /// Label color at `visibility` from 0 (blended into the background) to 1
+    /// (fully shown).
+    pub fn fade_label(&self, visibility: f32) -> Color {
+        fade(self.background, self.label, visibility)
+    }
+
+    /// Every color `t` of the way from this theme to `other`, from 0 to 1.
+    pub fn mix(&self, other: &Theme, t: f32) -> Theme {
+        let t = t.clamp(0., 1.);
+        let mix = |from: Color, to: Color| {
+            let channel =
+                |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+            Color::RGBA(
+                channel(from.r, to.r),
+                channel(from.g, to.g),
+                channel(from.b, to.b),
+                channel(from.a, to.a),
+            )
+        };
+        Theme {
+            digits: mix(self.digits, other.digits),
+            label: mix(self.label, other.label),
+            card: mix(self.card, other.card),
+            divider: mix(self.divider, other.divider),
+            divider_highlight: mix(self.divider_highlight, other.divider_highlight),
+            background: mix(self.background, other.background),
+        }
+    }
+
+    /// This theme at `brightness`, from 0 (black) to 1 (unchanged).
+    pub fn dimmed(&self, brightness: f32) -> Theme {
+        let black = |color: Color| Color::RGBA(0, 0, 0, color.a);
+        let dark = Theme {
+            digits: black(self.digits),
+            label: black(self.label),
+            card: black(self.card),
+            divider: black(self.divider),
+            divider_highlight: black(self.divider_highlight),
+            background: black(self.background),
+        };
+        self.mix(&dark, 1. - brightness)
+    }
+}
+
+/// The color `visibility` of the way from `from` to `to`, keeping the alpha of `to`.
+fn fade(from: Color, to: Color, visibility: f32) -> Color {
+    let t = visibility.clamp(0., 1.);
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+    Color::RGBA(
+        mix(from.r, to.r),
+        mix(from.g, to.g),
+        mix(from.b, to.b),
+        to.a,
+    )
+}
+
+/// Parse `"#rrggbb"` or `"#rrggbbaa"`; the leading `#` is optional.
+pub fn parse_color(value: &str) -> Result<Color> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    let invalid = || {
+        GluqloError::Config(format!(
+            "invalid color {:?}, expected #rrggbb or #rrggbbaa",
+            value
+        ))
+    };
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    let a = if hex.len() == 8 {
+        channel(6)?
+    } else {
+        DEFAULT_A
+    };
+    Ok(Color::RGBA(channel(0)?, channel(2)?, channel(4)?, a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors_with_and_without_alpha() {
+        assert_eq!(parse_color("#b7b7b7").unwrap(), rgb(0xb7, 0xb7, 0xb7));
+        assert_eq!(
+            parse_color("1a100080").unwrap(),
+            Color::RGBA(0x1a, 0x10, 0x00, 0x80)
+        );
+        assert!(parse_color("#12345").is_err());
+        assert!(parse_color("#gggggg").is_err());
+    }
+
+    #[test]
+    fn theme_file_overrides_its_base() {
+        let theme = Theme::from_toml("base = \"amber\"\ncard = \"#202020\"\n").unwrap();
+        assert_eq!(theme.digits, Theme::AMBER.digits);
+        assert_eq!(theme.card, rgb(0x20, 0x20, 0x20));
+
+        assert!(Theme::from_toml("digit = \"#ffffff\"").is_err());
+    }
+
+    #[test]
+    fn fade_runs_from_card_to_digits() {
+        assert_eq!(Theme::CLASSIC.fade(0.), Theme::CLASSIC.card);
+        assert_eq!(Theme::CLASSIC.fade(1.), Theme::CLASSIC.digits);
+        assert_eq!(Theme::LIGHT.fade_label(0.), Theme::LIGHT.background);
+        assert_eq!(Theme::LIGHT.fade_label(1.), Theme::LIGHT.label);
+    }
+
+    #[test]
+    fn mixes_and_dims_every_color() {
+        assert_eq!(Theme::CLASSIC.mix(&Theme::NIGHT, 0.), Theme::CLASSIC);
+        assert_eq!(Theme::CLASSIC.mix(&Theme::NIGHT, 1.), Theme::NIGHT);
+        let half = Theme::CLASSIC.mix(&Theme::LIGHT, 0.5);
+        assert_eq!(half.card, rgb(0x85, 0x85, 0x85));
+
+        let dim = Theme::AMBER.dimmed(0.5);
+        assert_eq!(dim.digits, rgb(0x80, 0x58, 0x00));
+        assert_eq!(Theme::AMBER.dimmed(1.), Theme::AMBER);
+    }
+}
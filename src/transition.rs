@@ -0,0 +1,299 @@
+use crate::paint::{half, Face, Painter};
+use crate::{FlipClockRenderer, GluqloError, Result};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::str::FromStr;
+
+/// Share of the light a flap folded edge-on still gets.
+const FOLD_AMBIENT: f32 = 0.35;
+/// Opacity of the shadow a flap casts on the lower half when edge-on.
+const FOLD_SHADOW: f64 = 96.;
+
+/// How a card turns over to the next value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Animation {
+    /// The flap shrinks toward the hinge and grows back, fading into the card.
+    #[default]
+    Flip,
+    /// The flap folds down around the hinge in perspective, lit from the front
+    /// and casting a shadow on the lower half.
+    Flip3d,
+    /// The new value rolls in from above and pushes the old one out below.
+    Slide,
+    /// The old digits fade out while the new ones fade in.
+    Crossfade,
+    /// The new value replaces the old one at once.
+    Instant,
+}
+
+impl Animation {
+    /// Every animation by the name `--transition` takes.
+    pub const NAMES: &'static [&'static str] = &[
+        "flip",
+        "flip3d",
+        "slide",
+        "roll",
+        "crossfade",
+        "none",
+        "instant",
+    ];
+
+    pub(crate) fn transition(self) -> &'static dyn Transition {
+        match self {
+            Animation::Flip => &Flip,
+            Animation::Flip3d => &Fold,
+            Animation::Slide => &Slide,
+            Animation::Crossfade => &Crossfade,
+            Animation::Instant => &Instant,
+        }
+    }
+}
+
+impl FromStr for Animation {
+    type Err = GluqloError;
+
+    fn from_str(s: &str) -> Result<Animation> {
+        match s {
+            "flip" => Ok(Animation::Flip),
+            "flip3d" => Ok(Animation::Flip3d),
+            "slide" | "roll" => Ok(Animation::Slide),
+            "crossfade" => Ok(Animation::Crossfade),
+            "none" | "instant" => Ok(Animation::Instant),
+            _ => Err(GluqloError::Config(format!(
+                "unknown transition {:?}, expected one of {}",
+                s,
+                Animation::NAMES.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Draws a card part of the way from one value to the next.
+pub(crate) trait Transition {
+    /// Draw `step` of `maxsteps` of the card at `card` turning from
+    /// `prevdigits` to `digits`. The last step, `maxsteps - 1`, shows `digits`
+    /// on the whole card.
+    #[allow(clippy::too_many_arguments)]
+    fn draw(
+        &self,
+        clock: &FlipClockRenderer,
+        painter: &mut dyn Painter,
+        card: Rect,
+        digits: &str,
+        prevdigits: &str,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()>;
+}
+
+/// How far along `step` of `maxsteps` is, from 0 to 1.
+pub(crate) fn progress(maxsteps: i32, step: i32) -> f64 {
+    (step as f64 / (maxsteps - 1).max(1) as f64).clamp(0., 1.)
+}
+
+/// The faces of the card in the theme's digit color.
+fn faces<'a>(
+    clock: &FlipClockRenderer,
+    digits: &'a str,
+    prevdigits: &'a str,
+) -> (Face<'a>, Face<'a>) {
+    let color = clock.options().theme.digits;
+    (
+        Face { digits, color },
+        Face {
+            digits: prevdigits,
+            color,
+        },
+    )
+}
+
+/// The classic split-flap: the upper flap shrinks toward the hinge, then the
+/// lower one grows from it.
+struct Flip;
+
+impl Transition for Flip {
+    fn draw(
+        &self,
+        clock: &FlipClockRenderer,
+        painter: &mut dyn Painter,
+        card: Rect,
+        digits: &str,
+        prevdigits: &str,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()> {
+        let theme = &clock.options().theme;
+        let (new, _) = faces(clock, digits, prevdigits);
+        painter.card_half(clock, card, new, true)?;
+
+        let halfsteps = maxsteps / 2;
+        let upperhalf = (step + 1) <= halfsteps;
+        let scale = if upperhalf {
+            1.0 - step as f64 / (halfsteps as f64 - 1.)
+        } else {
+            (step as f64 - halfsteps as f64 + 1.) / halfsteps as f64
+        };
+
+        // The flap darkens into the card as it turns edge-on.
+        let visibility = if upperhalf {
+            1. - step as f32 / (halfsteps as f32 - 1.)
+        } else {
+            (step as f32 - halfsteps as f32 + 1.) / halfsteps as f32
+        };
+
+        let flap = Face {
+            digits: if upperhalf { prevdigits } else { digits },
+            color: theme.fade(visibility),
+        };
+        painter.flap(clock, card, flap, upperhalf, scale)
+    }
+}
+
+/// The upper flap folds down over the lower half in perspective.
+struct Fold;
+
+impl Transition for Fold {
+    fn draw(
+        &self,
+        clock: &FlipClockRenderer,
+        painter: &mut dyn Painter,
+        card: Rect,
+        digits: &str,
+        prevdigits: &str,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()> {
+        let angle = PI * progress(maxsteps, step);
+        let (new, old) = faces(clock, digits, prevdigits);
+
+        // The flap reaches past the card while it is near the viewer.
+        painter.fill(Some(card), clock.options().theme.background)?;
+        painter.card_half(clock, card, new, true)?;
+        painter.card_half(clock, card, old, false)?;
+        let shadow = (FOLD_SHADOW * angle.sin()).round() as u8;
+        if shadow > 0 {
+            painter.shade(half(card, false), shadow)?;
+        }
+
+        let upper = angle < FRAC_PI_2;
+        let (face, tilt) = if upper {
+            (old, angle)
+        } else {
+            (new, PI - angle)
+        };
+        // Lit from the front, so the flap darkens as it turns edge-on.
+        let light = FOLD_AMBIENT + (1. - FOLD_AMBIENT) * tilt.cos() as f32;
+        painter.fold(clock, card, face, upper, tilt, light)
+    }
+}
+
+/// The cards roll down like a drum: the new value enters from the top.
+struct Slide;
+
+impl Transition for Slide {
+    fn draw(
+        &self,
+        clock: &FlipClockRenderer,
+        painter: &mut dyn Painter,
+        card: Rect,
+        digits: &str,
+        prevdigits: &str,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()> {
+        let t = progress(maxsteps, step);
+        // Ease in and out so the roll starts and stops gently.
+        let eased = t * t * (3. - 2. * t);
+        let offset = (eased * card.height() as f64).round() as i32;
+        let (new, old) = faces(clock, digits, prevdigits);
+
+        painter.fill(Some(card), clock.options().theme.background)?;
+        let mut at = card;
+        at.set_y(card.y() + offset);
+        painter.card(clock, at, old, card)?;
+        at.set_y(card.y() + offset - card.height() as i32);
+        painter.card(clock, at, new, card)
+    }
+}
+
+/// The digits blend from the old value into the new one on a still card.
+struct Crossfade;
+
+impl Transition for Crossfade {
+    fn draw(
+        &self,
+        clock: &FlipClockRenderer,
+        painter: &mut dyn Painter,
+        card: Rect,
+        digits: &str,
+        prevdigits: &str,
+        maxsteps: i32,
+        step: i32,
+    ) -> Result<()> {
+        let t = progress(maxsteps, step);
+        let (new, old) = faces(clock, digits, prevdigits);
+        let theme = &clock.options().theme;
+
+        let fading = Face {
+            color: theme.fade(1. - t as f32),
+            ..old
+        };
+        painter.card(clock, card, fading, card)?;
+        let alpha = (t * 255.).round() as u8;
+        if alpha > 0 {
+            let Color { r, g, b, a } = new.color;
+            let appearing = Face {
+                color: Color::RGBA(r, g, b, (a as u16 * alpha as u16 / 255) as u8),
+                ..new
+            };
+            painter.digits(clock, card, appearing, card)?;
+        }
+        Ok(())
+    }
+}
+
+/// No animation: every step shows the new value.
+struct Instant;
+
+impl Transition for Instant {
+    fn draw(
+        &self,
+        clock: &FlipClockRenderer,
+        painter: &mut dyn Painter,
+        card: Rect,
+        digits: &str,
+        prevdigits: &str,
+        _maxsteps: i32,
+        _step: i32,
+    ) -> Result<()> {
+        let (new, _) = faces(clock, digits, prevdigits);
+        painter.card(clock, card, new, card)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_name_and_alias() {
+        for name in Animation::NAMES {
+            assert!(name.parse::<Animation>().is_ok(), "{}", name);
+        }
+        assert_eq!("roll".parse::<Animation>().unwrap(), Animation::Slide);
+        assert_eq!("none".parse::<Animation>().unwrap(), Animation::Instant);
+        assert_eq!("instant".parse::<Animation>().unwrap(), Animation::Instant);
+        assert_eq!("flip3d".parse::<Animation>().unwrap(), Animation::Flip3d);
+        assert!("Flip".parse::<Animation>().is_err());
+        assert!("fade".parse::<Animation>().is_err());
+    }
+
+    #[test]
+    fn progress_runs_from_first_to_last_step() {
+        assert_eq!(progress(100, 0), 0.);
+        assert_eq!(progress(100, 99), 1.);
+        assert_eq!(progress(100, 150), 1.);
+        assert_eq!(progress(1, 0), 0.);
+    }
+}
@@ -0,0 +1,85 @@
+//! Running as an XScreenSaver hack.
+//!
+//! XScreenSaver starts hacks with X11-style single-dash options: `-root` to draw on
+//! the screen, in which case the window to use is in `$XSCREENSAVER_WINDOW`, or
+//! `-window-id <XID>` to draw into a preview window.
+
+use gluqlo::{GluqloError, Result};
+use sdl2::video::Window;
+use sdl2::VideoSubsystem;
+use std::env;
+use std::ffi::OsString;
+
+/// Rewrite XScreenSaver's `-root` and `-window-id` to the `--` form structopt expects.
+pub fn normalize_args<I: IntoIterator<Item = OsString>>(args: I) -> Vec<OsString> {
+    args.into_iter()
+        .map(|arg| match arg.to_str() {
+            Some("-root") => OsString::from("--root"),
+            Some("-window-id") => OsString::from("--window-id"),
+            _ => arg,
+        })
+        .collect()
+}
+
+/// Parse an X window id, either hex (`0x1a00003`) or decimal.
+pub fn parse_xid(s: &str) -> std::result::Result<u64, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|e| format!("invalid window id {:?}: {}", s, e))
+}
+
+/// The X window to draw into, or `None` to open a window of our own.
+pub fn target_window(root: bool, window_id: Option<u64>) -> Result<Option<u64>> {
+    if window_id.is_some() {
+        return Ok(window_id);
+    }
+    if !root {
+        return Ok(None);
+    }
+    match env::var("XSCREENSAVER_WINDOW") {
+        Ok(xid) => parse_xid(xid.trim())
+            .map(Some)
+            .map_err(|e| GluqloError::Config(format!("$XSCREENSAVER_WINDOW: {}", e))),
+        Err(_) => root_window().map(Some),
+    }
+}
+
+/// Wrap the existing X window `xid` in an SDL window.
+pub fn window_from_xid(video_subsystem: &VideoSubsystem, xid: u64) -> Result<Window> {
+    // SDL keeps the native window and never destroys it, it only stops drawing to it.
+    let raw = unsafe { sdl2::sys::SDL_CreateWindowFrom(xid as usize as *const _) };
+    if raw.is_null() {
+        return Err(GluqloError::Sdl(format!(
+            "cannot use window {:#x}: {}",
+            xid,
+            sdl2::get_error()
+        )));
+    }
+    Ok(unsafe { Window::from_ll(video_subsystem.clone(), raw) })
+}
+
+/// The root window of the default X display.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn root_window() -> Result<u64> {
+    use x11_dl::xlib::Xlib;
+
+    let xlib = Xlib::open().map_err(|e| GluqloError::Sdl(format!("cannot load Xlib: {}", e)))?;
+    unsafe {
+        let display = (xlib.XOpenDisplay)(std::ptr::null());
+        if display.is_null() {
+            return Err(GluqloError::Sdl("cannot open X display".to_owned()));
+        }
+        let root = (xlib.XDefaultRootWindow)(display);
+        (xlib.XCloseDisplay)(display);
+        Ok(root as u64)
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn root_window() -> Result<u64> {
+    Err(GluqloError::Config(
+        "-root needs X11 or $XSCREENSAVER_WINDOW".to_owned(),
+    ))
+}
@@ -0,0 +1,158 @@
+use crate::{GluqloError, Result, Time};
+use chrono::{DateTime, NaiveDate, TimeZone as _, Utc};
+use std::env;
+use std::fmt;
+use tz::TimeZone;
+
+/// A time zone read from the system tz database (`/usr/share/zoneinfo`), so no
+/// network access or bundled data is needed.
+#[derive(Debug, Clone)]
+pub struct Zone {
+    name: String,
+    tz: TimeZone,
+}
+
+impl Zone {
+    /// The zone in `$TZ`, else the system zone in `/etc/localtime`, else UTC.
+    pub fn local() -> Zone {
+        if let Ok(name) = env::var("TZ") {
+            if let Ok(zone) = Zone::named(&name) {
+                return zone;
+            }
+        }
+        let (name, tz) = match TimeZone::local() {
+            Ok(tz) => ("Local", tz),
+            Err(_) => ("UTC", TimeZone::utc()),
+        };
+        Zone {
+            name: name.to_owned(),
+            tz,
+        }
+    }
+
+    /// A zone by tz database name such as `Europe/Berlin`, or a POSIX TZ string.
+    pub fn named(name: &str) -> Result<Zone> {
+        let tz = TimeZone::from_posix_tz(name)
+            .map_err(|e| GluqloError::Config(format!("unknown time zone {:?}: {}", name, e)))?;
+        Ok(Zone {
+            name: name.trim_start_matches(':').to_owned(),
+            tz,
+        })
+    }
+
+    /// The name the zone was looked up by.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// A short caption for the zone: the city of `Area/City` names, with spaces.
+    ///
+    /// `America/New_York` becomes `New York`, `UTC` stays `UTC`.
+    pub fn city(&self) -> String {
+        self.name
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.name)
+            .replace('_', " ")
+    }
+
+    /// The wall-clock time in this zone at `instant`.
+    pub fn time_at(&self, instant: DateTime<Utc>) -> Result<Time> {
+        let date_time = tz::DateTime::from_timespec(instant.timestamp(), 0, self.tz.as_ref())
+            .map_err(|e| GluqloError::Render(format!("time in {}: {}", self, e)))?;
+        let time = Time::new(date_time.hour() as u32, date_time.minute() as u32)
+            .with_second(date_time.second() as u32);
+        let date = NaiveDate::from_ymd_opt(
+            date_time.year(),
+            date_time.month() as u32,
+            date_time.month_day() as u32,
+        );
+        Ok(match date {
+            Some(date) => time.with_date(date),
+            None => time,
+        })
+    }
+
+    /// The instant at which this zone shows `time`, on the day that contains `day_of`.
+    ///
+    /// A time skipped by a daylight saving change is an error; of a time that
+    /// occurs twice the earlier one is used.
+    pub fn instant_on_day(&self, day_of: DateTime<Utc>, time: Time) -> Result<DateTime<Utc>> {
+        let error = |e: &dyn fmt::Display| GluqloError::Config(format!("time in {}: {}", self, e));
+        let today = tz::DateTime::from_timespec(day_of.timestamp(), 0, self.tz.as_ref())
+            .map_err(|e| error(&e))?;
+        let found = tz::DateTime::find(
+            today.year(),
+            today.month(),
+            today.month_day(),
+            time.hour as u8,
+            time.minute as u8,
+            time.second as u8,
+            0,
+            self.tz.as_ref(),
+        )
+        .map_err(|e| error(&e))?;
+        found
+            .earliest()
+            .and_then(|date_time| Utc.timestamp_opt(date_time.unix_time(), 0).single())
+            .ok_or_else(|| {
+                error(&format!(
+                    "{:02}:{:02} does not exist on that day",
+                    time.hour, time.minute
+                ))
+            })
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2021-07-01 12:00:00 UTC.
+    fn noon() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2021, 7, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn reads_named_zones_from_the_system_database() {
+        let tokyo = Zone::named("Asia/Tokyo").unwrap();
+        assert_eq!(
+            tokyo.time_at(Utc.timestamp_opt(0, 0).unwrap()).unwrap(),
+            Time::new(9, 0).with_date(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap())
+        );
+        // During daylight saving time in Berlin.
+        let berlin = Zone::named("Europe/Berlin").unwrap();
+        assert_eq!(
+            berlin.time_at(noon()).unwrap(),
+            Time::new(14, 0).with_date(NaiveDate::from_ymd_opt(2021, 7, 1).unwrap())
+        );
+
+        assert!(Zone::named("Europe/Nowhere").is_err());
+    }
+
+    #[test]
+    fn finds_a_time_on_the_same_day() {
+        let zone = Zone::named("America/New_York").unwrap();
+        let instant = zone.instant_on_day(noon(), Time::new(11, 58)).unwrap();
+        assert_eq!(
+            instant,
+            Utc.with_ymd_and_hms(2021, 7, 1, 15, 58, 0).unwrap()
+        );
+        assert_eq!(
+            zone.time_at(instant).unwrap(),
+            Time::new(11, 58).with_date(NaiveDate::from_ymd_opt(2021, 7, 1).unwrap())
+        );
+    }
+
+    #[test]
+    fn captions_are_city_names() {
+        assert_eq!(Zone::named("America/New_York").unwrap().city(), "New York");
+        assert_eq!(Zone::named("UTC").unwrap().city(), "UTC");
+    }
+}
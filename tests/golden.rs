@@ -0,0 +1,709 @@
+//! Golden-image regression tests for the flip renderer.
+//!
+//! Every case renders a fixed time and animation step offscreen and compares the
+//! result against `tests/golden/<name>.png`. Pixels may differ by a small amount to
+//! absorb FreeType/SDL_gfx version differences; on failure the actual frame and a
+//! diff image are written to `target/golden/`. The `canvas_*` cases instead
+//! compare frames of the accelerated path with the surface renderer's.
+//!
+//! Run with `GLUQLO_BLESS=1 cargo test` to record or rewrite the references. A
+//! missing reference fails the case like a mismatch does.
+
+use chrono::NaiveDate;
+use gluqlo::{Animation, FlipClockRenderer, Options, TextureCache, Theme, Time};
+use sdl2::image::{LoadSurface, SaveSurface};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::BlendMode;
+use sdl2::render::Canvas;
+use sdl2::surface::{Surface, SurfaceRef};
+use sdl2::ttf::Sdl2TtfContext;
+use std::env;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, Once};
+
+/// Largest per-channel difference that still counts as a matching pixel.
+const CHANNEL_TOLERANCE: u8 = 24;
+/// Fraction of pixels allowed to exceed `CHANNEL_TOLERANCE`.
+const MAX_MISMATCH_RATIO: f64 = 0.002;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 480;
+
+static INIT: Once = Once::new();
+/// SDL and SDL_ttf are not thread safe, so cases run one at a time.
+static SDL_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    INIT.call_once(|| env::set_var("SDL_VIDEODRIVER", "dummy"));
+    SDL_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Run `f` with SDL video on the dummy driver and SDL_ttf, holding the lock.
+fn with_ttf<R>(f: impl FnOnce(&Sdl2TtfContext) -> R) -> R {
+    let _guard = lock();
+
+    let sdl_context = sdl2::init().unwrap();
+    let _video = sdl_context.video().unwrap();
+    let ttf_context = sdl2::ttf::init().unwrap();
+    f(&ttf_context)
+}
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn output_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden")
+}
+
+/// A time written as `(hour, minute)` or `(hour, minute, second)`, or a [`Time`].
+trait IntoTime: Copy {
+    fn into_time(self) -> Time;
+}
+
+impl IntoTime for (u32, u32) {
+    fn into_time(self) -> Time {
+        Time::new(self.0, self.1)
+    }
+}
+
+impl IntoTime for (u32, u32, u32) {
+    fn into_time(self) -> Time {
+        Time::new(self.0, self.1).with_second(self.2)
+    }
+}
+
+impl IntoTime for Time {
+    fn into_time(self) -> Time {
+        self
+    }
+}
+
+/// A new frame of `clock` at `step` of `maxsteps`, flipping from `prev` (or
+/// from nothing when `None`) to `now`.
+fn draw_step(
+    clock: &FlipClockRenderer,
+    now: Time,
+    prev: Option<Time>,
+    maxsteps: i32,
+    step: i32,
+) -> Surface<'static> {
+    let mut surface = Surface::new(WIDTH, HEIGHT, PixelFormatEnum::RGBA32).unwrap();
+    clock.clear(&mut surface).unwrap();
+    clock
+        .render(&mut surface, now, prev, maxsteps, step)
+        .unwrap();
+    surface
+}
+
+/// A new frame of `clock` showing 13:37.
+fn draw(clock: &FlipClockRenderer) -> Surface<'static> {
+    draw_step(clock, Time::new(13, 37), None, 20, 19)
+}
+
+/// Render `now` at `step` of `maxsteps`, flipping from `prev` (or from nothing when `None`).
+fn render<T: IntoTime>(
+    options: Options,
+    now: T,
+    prev: Option<T>,
+    maxsteps: i32,
+    step: i32,
+) -> Surface<'static> {
+    with_ttf(|ttf_context| {
+        let clock = FlipClockRenderer::new(ttf_context, WIDTH, HEIGHT, options).unwrap();
+        draw_step(
+            &clock,
+            now.into_time(),
+            prev.map(IntoTime::into_time),
+            maxsteps,
+            step,
+        )
+    })
+}
+
+/// Like [`render`], through SDL's software renderer instead of surface blits.
+fn render_canvas<T: IntoTime>(
+    options: Options,
+    now: T,
+    prev: Option<T>,
+    maxsteps: i32,
+    step: i32,
+) -> Surface<'static> {
+    with_ttf(|ttf_context| {
+        let surface = Surface::new(WIDTH, HEIGHT, PixelFormatEnum::RGBA32).unwrap();
+        let mut canvas = Canvas::from_surface(surface).unwrap();
+
+        let creator = canvas.texture_creator();
+        let mut textures = TextureCache::new(&creator);
+
+        let clock = FlipClockRenderer::new(ttf_context, WIDTH, HEIGHT, options).unwrap();
+        clock.clear_canvas(&mut canvas).unwrap();
+        clock
+            .render_canvas(
+                &mut canvas,
+                &mut textures,
+                now.into_time(),
+                prev.map(IntoTime::into_time),
+                maxsteps,
+                step,
+            )
+            .unwrap();
+
+        drop(textures);
+        canvas.into_surface()
+    })
+}
+
+fn seconds() -> Options {
+    Options {
+        seconds: true,
+        ..Options::default()
+    }
+}
+
+fn animated(animation: Animation) -> Options {
+    Options {
+        animation,
+        ..Options::default()
+    }
+}
+
+fn dated() -> Options {
+    Options {
+        date: Some("%a %-d %b".to_owned()),
+        ..Options::default()
+    }
+}
+
+/// `hour:minute` on October `day`, 2025.
+fn october(day: u32, hour: u32, minute: u32) -> Time {
+    Time::new(hour, minute).with_date(NaiveDate::from_ymd_opt(2025, 10, day).unwrap())
+}
+
+fn ampm() -> Options {
+    Options {
+        ampm: true,
+        ..Options::default()
+    }
+}
+
+/// Tightly packed RGBA bytes of an RGBA32 surface.
+fn pixels(surface: &SurfaceRef) -> Vec<u8> {
+    let row = surface.width() as usize * 4;
+    let pitch = surface.pitch() as usize;
+    surface.with_lock(|data| {
+        data.chunks(pitch)
+            .take(surface.height() as usize)
+            .flat_map(|line| line[..row].iter().copied())
+            .collect()
+    })
+}
+
+/// Build a diff image: mismatching pixels in red over a dimmed copy of the reference.
+fn diff_image(width: u32, height: u32, expected: &[u8], actual: &[u8]) -> Surface<'static> {
+    let mut diff = Surface::new(width, height, PixelFormatEnum::RGBA32).unwrap();
+    let row = width as usize * 4;
+    let pitch = diff.pitch() as usize;
+    diff.with_lock_mut(|data| {
+        for (y, line) in data.chunks_mut(pitch).take(height as usize).enumerate() {
+            for x in 0..width as usize {
+                let i = y * row + x * 4;
+                let out = &mut line[x * 4..x * 4 + 4];
+                if pixel_matches(&expected[i..i + 4], &actual[i..i + 4]) {
+                    let gray = expected[i..i + 3].iter().map(|&c| c as u32).sum::<u32>() / 12;
+                    out.copy_from_slice(&[gray as u8, gray as u8, gray as u8, 0xff]);
+                } else {
+                    out.copy_from_slice(&[0xff, 0, 0, 0xff]);
+                }
+            }
+        }
+    });
+    diff
+}
+
+fn pixel_matches(expected: &[u8], actual: &[u8]) -> bool {
+    expected
+        .iter()
+        .zip(actual)
+        .all(|(&e, &a)| (e as i16 - a as i16).unsigned_abs() <= CHANNEL_TOLERANCE as u16)
+}
+
+/// Save `actual` as `target/golden/<name>.actual.png` and return its path.
+fn save_actual(name: &str, actual: &SurfaceRef) -> PathBuf {
+    std::fs::create_dir_all(output_dir()).unwrap();
+    let actual_path = output_dir().join(format!("{}.actual.png", name));
+    actual
+        .convert_format(PixelFormatEnum::RGBA32)
+        .unwrap()
+        .save(&actual_path)
+        .unwrap();
+    actual_path
+}
+
+fn assert_golden(name: &str, actual: &SurfaceRef) {
+    let reference = golden_dir().join(format!("{}.png", name));
+
+    if env::var_os("GLUQLO_BLESS").is_some() {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        actual
+            .convert_format(PixelFormatEnum::RGBA32)
+            .unwrap()
+            .save(&reference)
+            .unwrap();
+        eprintln!("recorded golden image {}", reference.display());
+        return;
+    }
+    if !reference.exists() {
+        panic!(
+            "{}: no reference {}; check {} and record it with GLUQLO_BLESS=1",
+            name,
+            reference.display(),
+            save_actual(name, actual).display()
+        );
+    }
+
+    let expected = Surface::from_file(&reference)
+        .unwrap_or_else(|e| panic!("cannot load {}: {}", reference.display(), e))
+        .convert_format(PixelFormatEnum::RGBA32)
+        .unwrap();
+    assert_matches(name, &reference.display().to_string(), &expected, actual);
+}
+
+/// Fail `name` if `actual` differs from `expected`, drawn by `source`, by more
+/// than the tolerances, saving the actual frame and a diff image.
+fn assert_matches(name: &str, source: &str, expected: &SurfaceRef, actual: &SurfaceRef) {
+    assert_eq!(
+        (expected.width(), expected.height()),
+        (actual.width(), actual.height()),
+        "{}: size differs from {}",
+        name,
+        source
+    );
+
+    let expected_pixels = pixels(expected);
+    let actual_pixels = pixels(actual);
+    let mismatched = expected_pixels
+        .chunks(4)
+        .zip(actual_pixels.chunks(4))
+        .filter(|(e, a)| !pixel_matches(e, a))
+        .count();
+    let total = (actual.width() * actual.height()) as usize;
+    let ratio = mismatched as f64 / total as f64;
+
+    if ratio > MAX_MISMATCH_RATIO {
+        let actual_path = save_actual(name, actual);
+        let diff_path = output_dir().join(format!("{}.diff.png", name));
+        diff_image(
+            actual.width(),
+            actual.height(),
+            &expected_pixels,
+            &actual_pixels,
+        )
+        .save(&diff_path)
+        .unwrap();
+        panic!(
+            "{}: {} of {} pixels ({:.3}%) differ from {}; see {} and {}",
+            name,
+            mismatched,
+            total,
+            ratio * 100.,
+            source,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+macro_rules! golden {
+    ($name:ident, $options:expr, $now:expr, $prev:expr, $maxsteps:expr, $step:expr) => {
+        #[test]
+        fn $name() {
+            let surface = render($options, $now, $prev, $maxsteps, $step);
+            assert_golden(stringify!($name), &surface);
+        }
+    };
+}
+
+golden!(static_24h, Options::default(), (13, 37), None, 20, 19);
+golden!(static_ones, Options::default(), (11, 11), None, 20, 19);
+golden!(
+    static_single_digit_hour,
+    Options::default(),
+    (9, 41),
+    None,
+    20,
+    19
+);
+golden!(
+    static_leading_zero,
+    Options {
+        leading_zero: true,
+        ..Options::default()
+    },
+    (9, 41),
+    None,
+    20,
+    19
+);
+golden!(static_am, ampm(), (1, 5), None, 20, 19);
+golden!(static_pm, ampm(), (23, 59), None, 20, 19);
+golden!(
+    static_scaled,
+    Options {
+        display_scale_factor: 0.8,
+        ..Options::default()
+    },
+    (13, 37),
+    None,
+    20,
+    19
+);
+
+golden!(static_date, dated(), october(18, 13, 37), None, 20, 19);
+golden!(
+    static_date_captioned,
+    Options {
+        caption: Some("Berlin".to_owned()),
+        ..dated()
+    },
+    october(18, 13, 37),
+    None,
+    20,
+    19
+);
+// Within the day only the minute card is drawn, the date line is left alone.
+golden!(
+    date_kept_over_a_minute,
+    dated(),
+    october(18, 13, 38),
+    Some(october(18, 13, 37)),
+    20,
+    19
+);
+golden!(
+    date_fade_50,
+    dated(),
+    october(18, 0, 0),
+    Some(october(17, 23, 59)),
+    100,
+    50
+);
+
+golden!(
+    flip_step_00,
+    Options::default(),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    0
+);
+golden!(
+    flip_step_25,
+    Options::default(),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    25
+);
+golden!(
+    flip_step_49,
+    Options::default(),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    49
+);
+golden!(
+    flip_step_50,
+    Options::default(),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    50
+);
+golden!(
+    flip_step_75,
+    Options::default(),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    75
+);
+golden!(
+    flip_step_99,
+    Options::default(),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    99
+);
+golden!(
+    flip_minute_only,
+    Options::default(),
+    (13, 38),
+    Some((13, 37)),
+    100,
+    30
+);
+golden!(
+    theme_amber,
+    Options {
+        theme: Theme::AMBER,
+        ..Options::default()
+    },
+    (13, 0),
+    Some((12, 59)),
+    100,
+    25
+);
+golden!(
+    theme_light,
+    Options {
+        theme: Theme::LIGHT,
+        ..Options::default()
+    },
+    (13, 37),
+    None,
+    20,
+    19
+);
+golden!(flip_ampm_noon, ampm(), (12, 0), Some((11, 59)), 100, 40);
+
+golden!(static_seconds, seconds(), (13, 37, 42), None, 20, 19);
+golden!(
+    static_seconds_ampm,
+    Options {
+        ampm: true,
+        ..seconds()
+    },
+    (9, 5, 7),
+    None,
+    20,
+    19
+);
+golden!(
+    flip_second_only,
+    seconds(),
+    (13, 37, 43),
+    Some((13, 37, 42)),
+    100,
+    25
+);
+golden!(
+    flip_seconds_minute,
+    seconds(),
+    (13, 38, 0),
+    Some((13, 37, 59)),
+    100,
+    75
+);
+golden!(
+    static_flip3d,
+    animated(Animation::Flip3d),
+    (13, 37),
+    None,
+    20,
+    19
+);
+golden!(
+    flip3d_step_25,
+    animated(Animation::Flip3d),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    25
+);
+golden!(
+    flip3d_step_75,
+    animated(Animation::Flip3d),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    75
+);
+golden!(
+    slide_step_50,
+    animated(Animation::Slide),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    50
+);
+golden!(
+    slide_step_99,
+    animated(Animation::Slide),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    99
+);
+golden!(
+    crossfade_step_50,
+    animated(Animation::Crossfade),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    50
+);
+golden!(
+    crossfade_step_99,
+    animated(Animation::Crossfade),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    99
+);
+golden!(
+    instant_step_50,
+    animated(Animation::Instant),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    50
+);
+golden!(
+    instant_step_99,
+    animated(Animation::Instant),
+    (13, 0),
+    Some((12, 59)),
+    100,
+    99
+);
+
+#[test]
+fn world_grid() {
+    let surface = with_ttf(|ttf_context| {
+        let mut surface = Surface::new(WIDTH, HEIGHT, PixelFormatEnum::RGBA32).unwrap();
+        let cities = [("Tokyo", 21, 37), ("London", 13, 37), ("New York", 8, 37)];
+        let options = Options::default();
+        let viewports = FlipClockRenderer::grid(&options, cities.len() as u32, WIDTH, HEIGHT);
+        assert_eq!(viewports.len(), cities.len());
+
+        for (&(city, hour, minute), viewport) in cities.iter().zip(viewports) {
+            let options = Options {
+                caption: Some(city.to_owned()),
+                ..options.clone()
+            };
+            let clock = FlipClockRenderer::with_viewport(ttf_context, viewport, options).unwrap();
+            if city == "Tokyo" {
+                clock.clear(&mut surface).unwrap();
+            }
+            clock
+                .render(&mut surface, Time::new(hour, minute), None, 20, 19)
+                .unwrap();
+        }
+        surface
+    });
+    assert_golden("world_grid", &surface);
+}
+
+/// Check that the accelerated path draws the static frame of `now` from
+/// textures as the surface renderer draws it.
+fn assert_canvas_matches(name: &str, options: Options, now: (u32, u32)) {
+    let expected = render(options.clone(), now, None, 20, 19);
+    let actual = render_canvas(options, now, None, 20, 19);
+    assert_matches(name, "the surface renderer", &expected, &actual);
+}
+
+#[test]
+fn canvas_static_24h() {
+    assert_canvas_matches("canvas_static_24h", Options::default(), (13, 37));
+}
+
+#[test]
+fn canvas_static_single_digit_hour() {
+    assert_canvas_matches(
+        "canvas_static_single_digit_hour",
+        Options::default(),
+        (9, 41),
+    );
+}
+
+#[test]
+fn canvas_static_pm() {
+    assert_canvas_matches("canvas_static_pm", ampm(), (23, 59));
+}
+
+#[test]
+fn flips_reuse_cached_glyphs_and_cards() {
+    with_ttf(|ttf_context| {
+        let mut surface = Surface::new(WIDTH, HEIGHT, PixelFormatEnum::RGBA32).unwrap();
+        let mut clock =
+            FlipClockRenderer::new(ttf_context, WIDTH, HEIGHT, Options::default()).unwrap();
+        let flip = |clock: &FlipClockRenderer, surface: &mut Surface| {
+            for step in 0..20 {
+                clock
+                    .render(
+                        surface,
+                        Time::new(13, 38),
+                        Some(Time::new(13, 37)),
+                        20,
+                        step,
+                    )
+                    .unwrap();
+            }
+        };
+
+        flip(&clock, &mut surface);
+        let first = clock.cache_stats();
+        assert!(first.misses > 0);
+        // A glyph per digit and a card per face, not another card per fade level.
+        assert!(first.entries < 10, "{}", first);
+        flip(&clock, &mut surface);
+        let second = clock.cache_stats();
+        assert_eq!(
+            second.misses, first.misses,
+            "the same flip again is all hits"
+        );
+        assert_eq!(second.entries, first.entries);
+        // Faded frames tint cached glyphs, and those lookups count too.
+        assert!(second.hits >= first.hits + 20, "{}", second);
+
+        clock.set_theme(Theme::AMBER).unwrap();
+        assert_eq!(clock.cache_stats().entries, 0);
+    });
+}
+
+#[test]
+fn relayout_matches_a_new_renderer() {
+    with_ttf(|ttf_context| {
+        let mut resized =
+            FlipClockRenderer::new(ttf_context, 320, 240, Options::default()).unwrap();
+        draw(&resized);
+        resized
+            .relayout(ttf_context, Rect::new(0, 0, WIDTH, HEIGHT))
+            .unwrap();
+        let fresh = FlipClockRenderer::new(ttf_context, WIDTH, HEIGHT, Options::default()).unwrap();
+        assert!(pixels(&draw(&resized)) == pixels(&draw(&fresh)));
+    });
+}
+
+#[test]
+fn offset_moves_the_whole_clock() {
+    with_ttf(|ttf_context| {
+        let options = Options {
+            display_scale_factor: 0.8,
+            ..Options::default()
+        };
+        let mut clock = FlipClockRenderer::new(ttf_context, WIDTH, HEIGHT, options).unwrap();
+        let (min, max) = clock.offset_range();
+        assert_eq!(min, -max);
+        assert!(max.x() >= 6 && max.y() >= 4);
+
+        let mut still = draw(&clock);
+        still.set_blend_mode(BlendMode::None).unwrap();
+        let mut moved = Surface::new(WIDTH, HEIGHT, PixelFormatEnum::RGBA32).unwrap();
+        clock.clear(&mut moved).unwrap();
+        still
+            .blit(None, &mut moved, Rect::new(6, 4, WIDTH, HEIGHT))
+            .unwrap();
+
+        clock.set_offset(Point::new(6, 4));
+        assert_eq!(clock.offset(), Point::new(6, 4));
+        assert!(pixels(&draw(&clock)) == pixels(&moved));
+
+        clock.set_offset(Point::new(10_000, -10_000));
+        assert_eq!(clock.offset(), Point::new(max.x(), min.y()));
+    });
+}
@@ -0,0 +1,77 @@
+//! Runs the binary as an XScreenSaver hack drawing into a window it did not create.
+//!
+//! Needs an X server, e.g. `xvfb-run cargo test --test xscreensaver`; without
+//! `$DISPLAY` the test is skipped.
+#![cfg(all(unix, not(target_os = "macos")))]
+
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use x11_dl::xlib::{Xlib, ZPixmap};
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+
+#[test]
+fn draws_into_foreign_window() {
+    if std::env::var_os("DISPLAY").is_none() {
+        eprintln!("skipping: no X display, run under xvfb-run");
+        return;
+    }
+
+    let xlib = Xlib::open().expect("cannot load Xlib");
+    unsafe {
+        let display = (xlib.XOpenDisplay)(std::ptr::null());
+        assert!(!display.is_null(), "cannot open X display");
+        let screen = (xlib.XDefaultScreen)(display);
+        let black = (xlib.XBlackPixel)(display, screen);
+        let window = (xlib.XCreateSimpleWindow)(
+            display,
+            (xlib.XRootWindow)(display, screen),
+            0,
+            0,
+            WIDTH,
+            HEIGHT,
+            0,
+            black,
+            black,
+        );
+        (xlib.XMapWindow)(display, window);
+        (xlib.XSync)(display, 0);
+
+        let mut hack = Command::new(env!("CARGO_BIN_EXE_gluqlo-rs"))
+            .args(["-window-id", &format!("{:#x}", window)])
+            .env("SDL_VIDEODRIVER", "x11")
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("cannot start gluqlo");
+
+        thread::sleep(Duration::from_secs(2));
+        let exited = hack.try_wait().unwrap();
+        let _ = hack.kill();
+        let _ = hack.wait();
+        assert!(exited.is_none(), "gluqlo exited early: {:?}", exited);
+
+        let image = (xlib.XGetImage)(
+            display,
+            window,
+            0,
+            0,
+            WIDTH,
+            HEIGHT,
+            (xlib.XAllPlanes)(),
+            ZPixmap,
+        );
+        assert!(!image.is_null(), "cannot read back the window");
+        let drawn = (0..HEIGHT as i32)
+            .step_by(4)
+            .flat_map(|y| (0..WIDTH as i32).step_by(4).map(move |x| (x, y)))
+            .filter(|&(x, y)| (xlib.XGetPixel)(image, x, y) != black)
+            .count();
+        (xlib.XDestroyImage)(image);
+        (xlib.XDestroyWindow)(display, window);
+        (xlib.XCloseDisplay)(display);
+
+        assert!(drawn > 0, "nothing was drawn into the window");
+    }
+}